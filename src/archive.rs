@@ -0,0 +1,325 @@
+use std::{collections::HashMap, fs::{self, File, OpenOptions}, io::{BufRead, BufReader, Read, Write}, path::PathBuf};
+use chrono::{DateTime, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+use flate2::{Compression, read::MultiGzDecoder, write::GzEncoder};
+use anyhow::{anyhow, bail, Context, Result};
+use log::*;
+
+use crate::RawScrape;
+
+// Every interval is written as a separate gzip member, so the file can be appended to,
+// and a truncated last member (dsar being killed) only loses that interval.
+// The uncompressed member contains:
+// DSAR-INTERVAL <interval timestamp> <number of scrapes>
+// followed by, per scrape:
+// DSAR-SCRAPE <host:port:endpoint> <scrape timestamp> <length of data in bytes>
+// <data>
+pub static ARCHIVE_INTERVAL_MARKER: &str = "DSAR-INTERVAL";
+pub static ARCHIVE_SCRAPE_MARKER: &str = "DSAR-SCRAPE";
+
+pub struct ArchiveWriter {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+    rotate_daily: bool,
+    first_timestamp: Option<DateTime<Utc>>,
+}
+
+impl ArchiveWriter {
+    pub fn new(
+        path: &str,
+        max_size_mb: Option<u64>,
+        rotate_daily: bool,
+    ) -> Result<Self>
+    {
+        let path = PathBuf::from(path);
+        let file = OpenOptions::new().create(true).append(true).open(&path).with_context(|| format!("opening archive file {}", path.display()))?;
+        let metadata = file.metadata()?;
+        // an existing archive is appended to; the day of the last write decides daily rotation.
+        let first_timestamp = if metadata.len() > 0 { metadata.modified().ok().map(DateTime::<Utc>::from) } else { None };
+        Ok(Self {
+            path,
+            file,
+            size: metadata.len(),
+            max_size: max_size_mb.map(|megabytes| megabytes * 1024 * 1024),
+            rotate_daily,
+            first_timestamp,
+        })
+    }
+    pub fn write(
+        &mut self,
+        interval_timestamp: DateTime<Utc>,
+        raw_scrapes: &HashMap<String, RawScrape>,
+    ) -> Result<()>
+    {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        // scrapes that returned nothing (host down, wrong port/endpoint) are not stored.
+        let mut scrapes: Vec<_> = raw_scrapes.iter().filter(|(_, raw_scrape)| !raw_scrape.data.is_empty()).collect();
+        scrapes.sort_by_key(|(hostname_port, _)| *hostname_port);
+        writeln!(encoder, "{} {} {}", ARCHIVE_INTERVAL_MARKER, interval_timestamp.to_rfc3339_opts(SecondsFormat::Micros, true), scrapes.len())?;
+        for (hostname_port, raw_scrape) in scrapes
+        {
+            writeln!(encoder, "{} {} {} {}", ARCHIVE_SCRAPE_MARKER, hostname_port, raw_scrape.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true), raw_scrape.data.len())?;
+            encoder.write_all(raw_scrape.data.as_bytes())?;
+            writeln!(encoder)?;
+        }
+        let member = encoder.finish()?;
+
+        if self.needs_rotation(interval_timestamp, member.len() as u64)
+        {
+            self.rotate()?;
+        }
+        self.file.write_all(&member).with_context(|| format!("writing archive file {}", self.path.display()))?;
+        self.file.flush()?;
+        self.size += member.len() as u64;
+        self.first_timestamp.get_or_insert(interval_timestamp);
+        debug!("archive: {}: written interval {}, {} bytes", self.path.display(), interval_timestamp, member.len());
+        Ok(())
+    }
+    fn needs_rotation(
+        &self,
+        interval_timestamp: DateTime<Utc>,
+        member_size: u64,
+    ) -> bool
+    {
+        if self.size == 0 { return false };
+        let size_exceeded = self.max_size.map(|max_size| self.size + member_size > max_size).unwrap_or(false);
+        let day_changed = self.rotate_daily && self.first_timestamp.map(|first_timestamp| first_timestamp.date_naive() != interval_timestamp.date_naive()).unwrap_or(false);
+        size_exceeded || day_changed
+    }
+    // the current archive is renamed to <archive>.<timestamp of its first interval>, and a new archive is started.
+    fn rotate(
+        &mut self,
+    ) -> Result<()>
+    {
+        let suffix = self.first_timestamp.unwrap_or_else(Utc::now).format("%Y%m%d-%H%M%S");
+        let mut rotated_path = self.path.clone().into_os_string();
+        rotated_path.push(format!(".{}", suffix));
+        fs::rename(&self.path, &rotated_path).with_context(|| format!("rotating archive file {}", self.path.display()))?;
+        info!("archive: rotated {} to {}", self.path.display(), PathBuf::from(&rotated_path).display());
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path).with_context(|| format!("opening archive file {}", self.path.display()))?;
+        self.size = 0;
+        self.first_timestamp = None;
+        Ok(())
+    }
+}
+
+/// The raw scrapes of a single interval, as read back from an archive.
+#[derive(Debug, Default)]
+pub struct ArchiveInterval {
+    pub timestamp: DateTime<Utc>,
+    pub raw_scrapes: HashMap<String, RawScrape>,
+}
+
+pub struct ArchiveReader {
+    path: PathBuf,
+    reader: BufReader<MultiGzDecoder<File>>,
+    finished: bool,
+}
+
+impl ArchiveReader {
+    pub fn new(
+        path: &str,
+    ) -> Result<Self>
+    {
+        let path = PathBuf::from(path);
+        let file = File::open(&path).with_context(|| format!("opening archive file {}", path.display()))?;
+        Ok(Self {
+            path,
+            reader: BufReader::new(MultiGzDecoder::new(file)),
+            finished: false,
+        })
+    }
+    fn read_interval(
+        &mut self,
+    ) -> Result<Option<ArchiveInterval>>
+    {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 { return Ok(None) };
+        let fields: Vec<&str> = line.trim_end().split(' ').collect();
+        if fields.len() != 3 || fields[0] != ARCHIVE_INTERVAL_MARKER
+        {
+            bail!("expected {} line, found: {}", ARCHIVE_INTERVAL_MARKER, line.trim_end());
+        }
+        let timestamp = DateTime::parse_from_rfc3339(fields[1]).with_context(|| format!("invalid interval timestamp {}", fields[1]))?.with_timezone(&Utc);
+        let number_of_scrapes: usize = fields[2].parse().with_context(|| format!("invalid number of scrapes {}", fields[2]))?;
+
+        let mut raw_scrapes = HashMap::new();
+        for _ in 0..number_of_scrapes
+        {
+            line.clear();
+            self.reader.read_line(&mut line)?;
+            let fields: Vec<&str> = line.trim_end().split(' ').collect();
+            if fields.len() != 4 || fields[0] != ARCHIVE_SCRAPE_MARKER
+            {
+                bail!("expected {} line, found: {}", ARCHIVE_SCRAPE_MARKER, line.trim_end());
+            }
+            let scrape_timestamp = DateTime::parse_from_rfc3339(fields[2]).with_context(|| format!("invalid scrape timestamp {}", fields[2]))?.with_timezone(&Utc);
+            let length: usize = fields[3].parse().with_context(|| format!("invalid scrape length {}", fields[3]))?;
+            // the data is followed by a newline, which is not part of the length.
+            let mut data = vec![0; length + 1];
+            self.reader.read_exact(&mut data)?;
+            data.truncate(length);
+            raw_scrapes.insert(fields[1].to_string(), RawScrape {
+                timestamp: scrape_timestamp,
+                data: String::from_utf8(data).with_context(|| format!("scrape data of {} is not valid utf-8", fields[1]))?,
+            });
+        }
+        Ok(Some(ArchiveInterval { timestamp, raw_scrapes }))
+    }
+}
+
+impl Iterator for ArchiveReader {
+    type Item = Result<ArchiveInterval>;
+
+    // an error ends the iteration: the data after a corrupt or truncated member can't be trusted.
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.finished { return None };
+        match self.read_interval()
+        {
+            Ok(Some(interval)) => Some(Ok(interval)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            },
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error.context(format!("reading archive file {}", self.path.display()))))
+            },
+        }
+    }
+}
+
+/// A start or end bound for replaying an archive, or for plotting saved historical data.
+/// A time of day applies to every day in the archive, a date and time to a single moment.
+/// Both are taken as UTC, which is the time dsar prints.
+#[derive(Debug, Copy, Clone)]
+pub enum TimeBound {
+    TimeOfDay(NaiveTime),
+    DateTime(DateTime<Utc>),
+}
+
+impl TimeBound {
+    pub fn parse(
+        bound: &str,
+    ) -> Result<Self>
+    {
+        if let Ok(time) = NaiveTime::parse_from_str(bound, "%H:%M:%S").or_else(|_| NaiveTime::parse_from_str(bound, "%H:%M"))
+        {
+            return Ok(TimeBound::TimeOfDay(time));
+        }
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(bound)
+        {
+            return Ok(TimeBound::DateTime(datetime.with_timezone(&Utc)));
+        }
+        NaiveDateTime::parse_from_str(bound, "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(bound, "%Y-%m-%dT%H:%M:%S"))
+            .map(|datetime| TimeBound::DateTime(datetime.and_utc()))
+            .map_err(|_| anyhow!("invalid time: {} (use HH:MM[:SS] or YYYY-MM-DD HH:MM:SS)", bound))
+    }
+    pub fn is_after(
+        &self,
+        timestamp: DateTime<Utc>,
+    ) -> bool
+    {
+        match self
+        {
+            TimeBound::TimeOfDay(time) => *time > timestamp.time(),
+            TimeBound::DateTime(datetime) => *datetime > timestamp,
+        }
+    }
+    pub fn is_before(
+        &self,
+        timestamp: DateTime<Utc>,
+    ) -> bool
+    {
+        match self
+        {
+            TimeBound::TimeOfDay(time) => *time < timestamp.time(),
+            TimeBound::DateTime(datetime) => *datetime < timestamp,
+        }
+    }
+    /// The first moment after the timestamp: a time of day is today, or tomorrow if it has passed already.
+    pub fn next_after(
+        &self,
+        timestamp: DateTime<Utc>,
+    ) -> DateTime<Utc>
+    {
+        match self
+        {
+            TimeBound::TimeOfDay(time) => {
+                let today = timestamp.date_naive().and_time(*time).and_utc();
+                if today > timestamp { today } else { today + chrono::Duration::days(1) }
+            },
+            TimeBound::DateTime(datetime) => *datetime,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn archive_round_trip() {
+        let path = std::env::temp_dir().join(format!("dsar-test-{}-round-trip.dsar", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let first = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let second = first + chrono::Duration::milliseconds(1500);
+        let intervals = [
+            (first, HashMap::from([
+                ("node1:9100:metrics".to_string(), RawScrape { timestamp: first, data: "node_load1 1.5\nnode_load5 1.2\n".to_string() }),
+                ("node2:9100:metrics".to_string(), RawScrape { timestamp: first, data: "node_load1 0.5".to_string() }),
+                // a failed scrape has no data, and is not stored.
+                ("node3:9100:metrics".to_string(), RawScrape { timestamp: first, data: String::new() }),
+            ])),
+            (second, HashMap::from([
+                ("node1:9100:metrics".to_string(), RawScrape { timestamp: second, data: "node_load1 1.7\n".to_string() }),
+            ])),
+        ];
+        // every write is a gzip member of its own, also across a reopen of the archive.
+        for (timestamp, raw_scrapes) in &intervals
+        {
+            ArchiveWriter::new(&path.to_string_lossy(), None, false).unwrap().write(*timestamp, raw_scrapes).unwrap();
+        }
+
+        let read: Vec<ArchiveInterval> = ArchiveReader::new(&path.to_string_lossy()).unwrap().collect::<Result<_>>().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.len(), 2);
+        for (interval, (timestamp, raw_scrapes)) in read.iter().zip(&intervals)
+        {
+            assert_eq!(interval.timestamp, *timestamp);
+            let stored: Vec<_> = raw_scrapes.iter().filter(|(_, raw_scrape)| !raw_scrape.data.is_empty()).collect();
+            assert_eq!(interval.raw_scrapes.len(), stored.len());
+            for (hostname_port, raw_scrape) in stored
+            {
+                assert_eq!(interval.raw_scrapes[hostname_port].timestamp, raw_scrape.timestamp);
+                assert_eq!(interval.raw_scrapes[hostname_port].data, raw_scrape.data);
+            }
+        }
+    }
+
+    #[test]
+    fn archive_truncated_member() {
+        let path = std::env::temp_dir().join(format!("dsar-test-{}-truncated.dsar", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let timestamp = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let raw_scrapes = HashMap::from([("node1:9100:metrics".to_string(), RawScrape { timestamp, data: "node_load1 1.5\n".to_string() })]);
+        let mut archive_writer = ArchiveWriter::new(&path.to_string_lossy(), None, false).unwrap();
+        archive_writer.write(timestamp, &raw_scrapes).unwrap();
+        archive_writer.write(timestamp + chrono::Duration::seconds(1), &raw_scrapes).unwrap();
+        // dsar killed halfway writing the last interval.
+        let length = fs::metadata(&path).unwrap().len();
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(length - 10).unwrap();
+
+        let read: Vec<Result<ArchiveInterval>> = ArchiveReader::new(&path.to_string_lossy()).unwrap().collect();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].as_ref().unwrap().timestamp, timestamp);
+        assert!(read[1].is_err());
+    }
+}
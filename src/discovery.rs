@@ -0,0 +1,260 @@
+use std::{collections::{BTreeMap, HashMap}, time::{Duration, Instant}};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, de::DeserializeOwned};
+use log::*;
+
+use crate::ScrapeOptions;
+use crate::target::Target;
+
+// the response of the yb-master /api/v1/tablet-servers endpoint:
+// { "<cluster uuid>": { "<host>:<webserver port>": { "status": "ALIVE", .. }, .. } }
+#[derive(Debug, Deserialize)]
+struct TabletServer {
+    #[serde(default)]
+    status: String,
+}
+
+// the response of the yb-master /api/v1/masters endpoint:
+// { "masters": [ { "registration": { "http_addresses": [ { "host": "<host>", "port": <webserver port> } ], .. }, "role": "LEADER", .. }, .. ] }
+#[derive(Debug, Deserialize)]
+struct Masters {
+    masters: Vec<Master>,
+}
+#[derive(Debug, Deserialize)]
+struct Master {
+    registration: Option<MasterRegistration>,
+}
+#[derive(Debug, Deserialize)]
+struct MasterRegistration {
+    #[serde(default)]
+    http_addresses: Vec<HostPort>,
+}
+#[derive(Debug, Deserialize)]
+struct HostPort {
+    host: String,
+    port: u16,
+}
+
+/// Discovery of the YugabyteDB tablet servers and masters via the yb-master webserver.
+/// For every tablet server and master, its prometheus-metrics endpoint and the node_exporter of the host are scraped.
+/// The client is kept between the refreshes, and a request to a master is abandoned after the timeout (the scrape interval),
+/// so a master that hangs doesn't stall the scrapes.
+pub struct YbDiscovery {
+    masters: Vec<Target>,
+    node_exporter_port: u16,
+    refresh_interval: Duration,
+    timeout: Duration,
+    client: reqwest::Client,
+    scrape_options: ScrapeOptions,
+    last_refresh: Option<Instant>,
+    pub targets: Vec<Target>,
+}
+
+impl YbDiscovery {
+    pub async fn new(
+        masters: &[&str],
+        node_exporter_port: u16,
+        refresh_interval: Duration,
+        timeout: Duration,
+        default_scheme: &str,
+        scrape_options: &ScrapeOptions,
+    ) -> Result<Self>
+    {
+        let mut yb_discovery = Self {
+            masters: masters.iter().map(|master| Target::parse(master, default_scheme)).collect::<Result<_>>()?,
+            node_exporter_port,
+            refresh_interval,
+            timeout,
+            client: scrape_options.async_client()?,
+            scrape_options: scrape_options.clone(),
+            last_refresh: None,
+            targets: Vec::new(),
+        };
+        yb_discovery.refresh_if_due().await?;
+        Ok(yb_discovery)
+    }
+    /// Query the masters again if the refresh interval has passed. Returns true if the targets changed.
+    /// When none of the masters can be queried, the current targets stay in use.
+    pub async fn refresh_if_due(
+        &mut self,
+    ) -> Result<bool>
+    {
+        if self.last_refresh.map(|last_refresh| last_refresh.elapsed() < self.refresh_interval).unwrap_or(false) { return Ok(false) };
+        self.last_refresh = Some(Instant::now());
+        let mut last_error = anyhow!("no yb-master addresses");
+        // any master can be asked; the first one that answers is used.
+        for master in &self.masters
+        {
+            match self.discover(master).await
+            {
+                Ok(targets) => {
+                    if targets == self.targets { return Ok(false) };
+                    if !self.targets.is_empty()
+                    {
+                        info!("yb-master {}:{}: {} targets (was {})", master.host, master.port, targets.len(), self.targets.len());
+                    }
+                    for target in &targets
+                    {
+                        debug!("yb-master {}:{}: target: {}", master.host, master.port, target.url());
+                    }
+                    self.targets = targets;
+                    return Ok(true);
+                },
+                Err(error) => {
+                    debug!("{:#}", error);
+                    last_error = error;
+                },
+            }
+        }
+        Err(last_error)
+    }
+    async fn discover(
+        &self,
+        master: &Target,
+    ) -> Result<Vec<Target>>
+    {
+        let tablet_servers: HashMap<String, HashMap<String, TabletServer>> = self.get_json(master, "api/v1/tablet-servers").await?;
+        let masters: Masters = self.get_json(master, "api/v1/masters").await?;
+
+        // the map is used to deduplicate: a host can run a master and a tablet server, but has one node_exporter.
+        let mut targets: BTreeMap<String, Target> = BTreeMap::new();
+        let mut add_yb_target = |host: &str, port: u16| {
+            for (port, path) in [(port, "prometheus-metrics"), (self.node_exporter_port, "metrics")]
+            {
+                let target = Target {
+                    scheme: master.scheme.clone(),
+                    host: host.to_string(),
+                    port,
+                    path: path.to_string(),
+                    labels: BTreeMap::new(),
+                };
+                targets.insert(target.name(), target);
+            }
+        };
+        for (host_port, tablet_server) in tablet_servers.values().flatten()
+        {
+            // a dead tablet server will not respond; it is scraped again once it's alive.
+            if tablet_server.status != "ALIVE"
+            {
+                debug!("yb-master {}:{}: tablet server {} status {}, skipped", master.host, master.port, host_port, tablet_server.status);
+                continue;
+            }
+            let Some((host, port)) = host_port.rsplit_once(':') else { continue };
+            let Ok(port) = port.parse() else { continue };
+            add_yb_target(host.trim_start_matches('[').trim_end_matches(']'), port);
+        }
+        for http_address in masters.masters.iter().filter_map(|master| master.registration.as_ref()).filter_map(|registration| registration.http_addresses.first())
+        {
+            add_yb_target(&http_address.host, http_address.port);
+        }
+        Ok(targets.into_values().collect())
+    }
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        master: &Target,
+        path: &str,
+    ) -> Result<T>
+    {
+        let url = format!("{}://{}:{}/{}", master.scheme, master.host, master.port, path);
+        let response = self.scrape_options.authenticate(self.client.get(&url).timeout(self.timeout)).send().await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("yb-master {}", url))?
+            .text().await
+            .with_context(|| format!("yb-master {}", url))?;
+        serde_json::from_str(&response).with_context(|| format!("yb-master {}: parsing response", url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+    // a yb-master webserver that answers the tablet-servers and masters endpoints with the responses given.
+    async fn serve_yb_master(
+        tablet_servers: &'static str,
+        masters: &'static str,
+    ) -> u16
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop
+            {
+                let Ok((mut stream, _)) = listener.accept().await else { break };
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n")
+                {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    if read == 0 { break };
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let (status, body) = match request.split_whitespace().nth(1)
+                {
+                    Some("/api/v1/tablet-servers") => ("200 OK", tablet_servers),
+                    Some("/api/v1/masters") => ("200 OK", masters),
+                    _ => ("404 Not Found", ""),
+                };
+                let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn discovers_tablet_servers_and_masters() {
+        let tablet_servers = r#"{ "b2a0e5c8": {
+            "127.0.0.1:9000": { "status": "ALIVE" },
+            "127.0.0.2:9000": { "status": "DEAD" },
+            "127.0.0.3:9000": { "status": "ALIVE" } } }"#;
+        let masters = r#"{ "masters": [
+            { "registration": { "http_addresses": [ { "host": "127.0.0.1", "port": 7000 } ] }, "role": "LEADER" },
+            { "registration": { "http_addresses": [ { "host": "127.0.0.4", "port": 7000 } ] }, "role": "FOLLOWER" } ] }"#;
+        let port = serve_yb_master(tablet_servers, masters).await;
+
+        let yb_discovery = YbDiscovery::new(&[&format!("127.0.0.1:{}", port)], 9300, Duration::from_secs(60), Duration::from_secs(5), "http", &ScrapeOptions::default()).await.unwrap();
+
+        // the dead tablet server is skipped, and 127.0.0.1, which runs a master and a tablet server, has one node_exporter target.
+        let targets: Vec<String> = yb_discovery.targets.iter().map(|target| target.name()).collect();
+        assert_eq!(targets, vec![
+            "127.0.0.1:7000:prometheus-metrics",
+            "127.0.0.1:9000:prometheus-metrics",
+            "127.0.0.1:9300:metrics",
+            "127.0.0.3:9000:prometheus-metrics",
+            "127.0.0.3:9300:metrics",
+            "127.0.0.4:7000:prometheus-metrics",
+            "127.0.0.4:9300:metrics",
+        ]);
+        assert!(yb_discovery.targets.iter().all(|target| target.scheme == "http"));
+    }
+
+    #[tokio::test]
+    async fn skips_a_master_that_hangs() {
+        // a master that accepts the connection, but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hanging_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await
+            {
+                streams.push(stream);
+            }
+        });
+        let port = serve_yb_master(r#"{ "b2a0e5c8": { "127.0.0.1:9000": { "status": "ALIVE" } } }"#, r#"{ "masters": [] }"#).await;
+
+        let started = Instant::now();
+        let yb_discovery = YbDiscovery::new(&[&format!("127.0.0.1:{}", hanging_port), &format!("127.0.0.1:{}", port)], 9300, Duration::from_secs(60), Duration::from_millis(500), "http", &ScrapeOptions::default()).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(yb_discovery.targets.iter().map(|target| target.name()).collect::<Vec<_>>(), vec!["127.0.0.1:9000:prometheus-metrics", "127.0.0.1:9300:metrics"]);
+    }
+
+    #[tokio::test]
+    async fn fails_without_a_master_that_answers() {
+        let port = serve_yb_master("{", "{").await;
+        assert!(YbDiscovery::new(&[&format!("127.0.0.1:{}", port)], 9300, Duration::from_secs(60), Duration::from_secs(5), "http", &ScrapeOptions::default()).await.is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::{fmt::Write as _, sync::{Arc, Mutex}, time::Duration};
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::{TcpListener, TcpStream}, time};
+use anyhow::{Context, Result};
+use log::*;
+
+use crate::Row;
+use crate::output::View;
+
+/// Serves the values of the views of the last interval as prometheus gauges on /metrics,
+/// so dsar can be scraped itself, for the values that node_exporter doesn't have, such as the iostat-x latencies and request sizes.
+/// Every interval replaces all values, so the hosts that are gone are not served anymore.
+#[derive(Clone)]
+pub struct Exporter {
+    metrics: Arc<Mutex<String>>,
+}
+
+impl Exporter {
+    pub async fn start(
+        listen: &str,
+    ) -> Result<Self>
+    {
+        let listener = TcpListener::bind(listen).await.with_context(|| format!("listening on {}", listen))?;
+        info!("serving the values on http://{}/metrics", listener.local_addr()?);
+        let exporter = Exporter { metrics: Default::default() };
+        let metrics = exporter.metrics.clone();
+        tokio::spawn(async move {
+            loop
+            {
+                match listener.accept().await
+                {
+                    Ok((stream, _)) => {
+                        let metrics = metrics.clone();
+                        tokio::spawn(async move {
+                            if let Err(error) = serve(stream, &metrics).await
+                            {
+                                debug!("serving metrics: {:#}", error);
+                            }
+                        });
+                    },
+                    Err(error) => warn!("accepting a connection on the metrics endpoint: {}", error),
+                }
+            }
+        });
+        Ok(exporter)
+    }
+    pub fn update(
+        &self,
+        views: &[(View, Vec<Row>)],
+    )
+    {
+        *self.metrics.lock().unwrap() = format_metrics(views);
+    }
+}
+
+/// The values in the prometheus text format: a gauge dsar_<view>_<field> per value, with the host, the cpu, device or interface and the extra labels of the row as labels.
+pub fn format_metrics(
+    views: &[(View, Vec<Row>)],
+) -> String
+{
+    let mut metrics = String::new();
+    for (view, rows) in views.iter().filter(|(_, rows)| !rows.is_empty())
+    {
+        let view_name = view.name.to_lowercase().replace('-', "_");
+        for (index, field) in view.fields.iter().enumerate()
+        {
+            let name = format!("dsar_{}_{}", view_name, field);
+            let _ = writeln!(metrics, "# HELP {} {} {}, as calculated by dsar", name, view.name, field);
+            let _ = writeln!(metrics, "# TYPE {} gauge", name);
+            for row in rows
+            {
+                let mut labels = match view.label_name
+                {
+                    Some(label_name) => format!("host=\"{}\",{}=\"{}\"", escape(&row.hostname), label_name, escape(&row.label)),
+                    None => format!("host=\"{}\"", escape(&row.hostname)),
+                };
+                for (name, value) in view.extra_labels(&row.labels)
+                {
+                    let _ = write!(labels, ",{}=\"{}\"", name, escape(value));
+                }
+                let _ = writeln!(metrics, "{}{{{}}} {}", name, labels, format_value(row.values[index]));
+            }
+        }
+    }
+    metrics
+}
+
+fn escape(
+    label_value: &str,
+) -> String
+{
+    label_value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(
+    value: f64,
+) -> String
+{
+    if value.is_nan() { "NaN".to_string() }
+    else if value.is_infinite() { if value > 0. { "+Inf".to_string() } else { "-Inf".to_string() } }
+    else { value.to_string() }
+}
+
+// a minimal http server: only GET /metrics is answered, and the connection is closed after every response.
+async fn serve(
+    mut stream: TcpStream,
+    metrics: &Mutex<String>,
+) -> Result<()>
+{
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192
+    {
+        let read = time::timeout(Duration::from_secs(10), stream.read(&mut buffer)).await.context("reading request")??;
+        if read == 0
+        {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request_line = String::from_utf8_lossy(&request).lines().next().unwrap_or_default().to_string();
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next())
+    {
+        (Some("GET"), Some(path)) if path == "/metrics" || path.starts_with("/metrics?") => {
+            let body = metrics.lock().unwrap().clone();
+            format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+        },
+        (Some("GET"), Some(_)) => "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: 10\r\nConnection: close\r\n\r\nnot found\n".to_string(),
+        _ => "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn metrics_text_format() {
+        let timestamp = Utc::now();
+        let views = vec![
+            (View { name: "sar-d".to_string(), label_name: Some("device"), fields: &["tps", "await"] }, vec![
+                Row { hostname: "node1:9100:metrics".to_string(), timestamp, label: "sda".to_string(), labels: vec![("env".to_string(), "pr\"od".to_string())], values: vec![10., f64::NAN] },
+            ]),
+            (View { name: "sar-q".to_string(), label_name: None, fields: &["ldavg_1"] }, vec![
+                Row { hostname: "node1:9100:metrics".to_string(), timestamp, label: String::new(), labels: Vec::new(), values: vec![f64::INFINITY] },
+            ]),
+            // a view without rows has no metrics.
+            (View { name: "sar-n-DEV".to_string(), label_name: Some("iface"), fields: &["rxpck_per_s"] }, Vec::new()),
+        ];
+        assert_eq!(format_metrics(&views), concat!(
+            "# HELP dsar_sar_d_tps sar-d tps, as calculated by dsar\n",
+            "# TYPE dsar_sar_d_tps gauge\n",
+            "dsar_sar_d_tps{host=\"node1:9100:metrics\",device=\"sda\",env=\"pr\\\"od\"} 10\n",
+            "# HELP dsar_sar_d_await sar-d await, as calculated by dsar\n",
+            "# TYPE dsar_sar_d_await gauge\n",
+            "dsar_sar_d_await{host=\"node1:9100:metrics\",device=\"sda\",env=\"pr\\\"od\"} NaN\n",
+            "# HELP dsar_sar_q_ldavg_1 sar-q ldavg_1, as calculated by dsar\n",
+            "# TYPE dsar_sar_q_ldavg_1 gauge\n",
+            "dsar_sar_q_ldavg_1{host=\"node1:9100:metrics\"} +Inf\n",
+        ));
+    }
+}
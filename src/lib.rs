@@ -1,24 +1,34 @@
 use std::time;
-use prometheus_parse::Scrape;
-use std::collections::{BTreeMap, HashMap};
-use std::sync::mpsc::channel;
+use prometheus_parse::{Sample, Scrape, Value};
+use std::collections::{btree_map, BTreeMap, HashMap};
+use std::fs::File;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::io::{BufReader, BufWriter};
 use time::Duration;
-use log::*;
 use chrono::{DateTime, Utc};
 use itertools::Itertools;
+use log::*;
+use serde::{Serialize, Deserialize};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use anyhow::{anyhow, Context, Result};
 
 use crate::node_cpu::NodeCpuDetails;
 use crate::node_disk::NodeDiskDetails;
 use crate::node_network::NodeNetworkDetails;
+use crate::node_netstat::NodeNetstatDetails;
 use crate::node_memory::NodeMemoryDetails;
 use crate::yb_memory::YbMemoryDetails;
 use crate::yb_io::YbIoDetails;
 use crate::node_misc::NodeMiscDetails;
 use crate::node_vmstat::NodeVmstatDetails;
+use crate::archive::TimeBound;
+use crate::output::RowWriter;
 
 pub mod node_cpu;
 pub mod node_disk;
 pub mod node_network;
+pub mod node_netstat;
 pub mod node_memory;
 pub mod node_vmstat;
 pub mod node_misc;
@@ -26,6 +36,12 @@ pub mod yb_cpu;
 pub mod yb_network;
 pub mod yb_memory;
 pub mod yb_io;
+pub mod archive;
+pub mod target;
+pub mod discovery;
+pub mod scraper;
+pub mod output;
+pub mod exporter;
 
 static LABEL_AREA_SIZE_LEFT: i32 = 100;
 static LABEL_AREA_SIZE_RIGHT: i32 = 100;
@@ -44,27 +60,529 @@ pub struct Statistic {
     pub per_second_value: f64,
     pub last_timestamp: DateTime<Utc>,
     pub first_value: bool,
+    pub reset: bool,
+}
+
+impl Statistic {
+    /// Update a counter with a new value, and calculate the difference with the previous value.
+    /// A counter can only go up, so a lower value means the counter was reset (the exporter or server restarted,
+    /// or the counter wrapped). The value then is taken as a new baseline, and the row is flagged as reset:
+    /// like a first value, it has no difference to show for this interval.
+    pub fn update_counter(
+        &mut self,
+        value: f64,
+        timestamp: DateTime<Utc>,
+    )
+    {
+        if value < self.last_value
+        {
+            debug!("counter reset: last_value: {}, value: {}", self.last_value, value);
+            self.delta_value = 0.;
+            self.per_second_value = 0.;
+            self.reset = true;
+        }
+        else
+        {
+            self.delta_value = value - self.last_value;
+            // microseconds, so the rate is exact for sub-second intervals too.
+            let seconds = timestamp.signed_duration_since(self.last_timestamp).num_microseconds().unwrap_or_default() as f64 / 1_000_000.0;
+            self.per_second_value = if seconds > 0. { self.delta_value / seconds } else { 0. };
+            self.reset = false;
+        }
+        self.last_value = value;
+        self.last_timestamp = timestamp;
+        self.first_value = self.reset;
+    }
+}
+
+/// The statistics of all targets, indexed by host, metric and labels.
+/// A host is the target name (host:port:path), the labels are the one or two labels that identify a row of a metric,
+/// such as cpu and mode, device, or metric_type and table_id; an unused label is "".
+/// Looking up a row is a lookup per level, instead of a scan over the statistics of all targets.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    hosts: BTreeMap<String, BTreeMap<String, MetricRows>>,
+}
+
+type MetricRows = BTreeMap<(String, String), Statistic>;
+
+impl Statistics {
+    pub fn entry(
+        &mut self,
+        hostname: &str,
+        metric: &str,
+        label: &str,
+        label2: &str,
+    ) -> btree_map::Entry<'_, (String, String), Statistic>
+    {
+        self.hosts
+            .entry(hostname.to_string())
+            .or_default()
+            .entry(metric.to_string())
+            .or_default()
+            .entry((label.to_string(), label2.to_string()))
+    }
+    /// The row of a metric with exactly these labels.
+    pub fn get(
+        &self,
+        hostname: &str,
+        metric: &str,
+        label: &str,
+        label2: &str,
+    ) -> Option<&Statistic>
+    {
+        self.hosts.get(hostname)?.get(metric)?.get(&(label.to_string(), label2.to_string()))
+    }
+    /// The first row of a metric, which is the only row of a metric without labels.
+    pub fn first(
+        &self,
+        hostname: &str,
+        metric: &str,
+    ) -> Option<&Statistic>
+    {
+        self.hosts.get(hostname)?.get(metric)?.values().next()
+    }
+    /// The first row of a metric with this (first) label.
+    pub fn first_with(
+        &self,
+        hostname: &str,
+        metric: &str,
+        label: &str,
+    ) -> Option<&Statistic>
+    {
+        self.hosts.get(hostname)?
+            .get(metric)?
+            .range((label.to_string(), String::new())..)
+            .next()
+            .filter(|((row_label, _), _)| row_label == label)
+            .map(|(_, statistic)| statistic)
+    }
+    /// Whether a row with this (first) label, such as a device, of one of the metrics starting with the prefix took a new baseline
+    /// (a first value, or a counter reset) in this interval, so it has no difference to show.
+    pub fn new_baseline_with(
+        &self,
+        hostname: &str,
+        prefix: &str,
+        label: &str,
+    ) -> bool
+    {
+        self.hosts.get(hostname).into_iter().flatten()
+            .filter(|(metric, _)| metric.starts_with(prefix))
+            .any(|(metric, _)| self.first_with(hostname, metric, label).map(|statistic| statistic.first_value).unwrap_or(false))
+    }
+    /// All rows of a metric, ordered by their labels.
+    pub fn rows(
+        &self,
+        hostname: &str,
+        metric: &str,
+    ) -> impl Iterator<Item = (&(String, String), &Statistic)>
+    {
+        self.hosts.get(hostname).and_then(|metrics| metrics.get(metric)).into_iter().flatten()
+    }
+    pub fn contains_metric(
+        &self,
+        hostname: &str,
+        metric: &str,
+    ) -> bool
+    {
+        self.hosts.get(hostname).map(|metrics| metrics.contains_key(metric)).unwrap_or(false)
+    }
+    pub fn hosts(&self) -> impl Iterator<Item = &String> {
+        self.hosts.keys()
+    }
+    /// All rows of a host.
+    pub fn host_rows(
+        &self,
+        hostname: &str,
+    ) -> impl Iterator<Item = &Statistic>
+    {
+        self.hosts.get(hostname).into_iter().flat_map(|metrics| metrics.values()).flat_map(|rows| rows.values())
+    }
+    pub fn host_rows_mut(
+        &mut self,
+        hostname: &str,
+    ) -> impl Iterator<Item = &mut Statistic>
+    {
+        self.hosts.get_mut(hostname).into_iter().flat_map(|metrics| metrics.values_mut()).flat_map(|rows| rows.values_mut())
+    }
+    pub fn retain_hosts<F>(
+        &mut self,
+        mut keep: F,
+    )
+    where
+        F: FnMut(&String) -> bool,
+    {
+        self.hosts.retain(|hostname, _| keep(hostname));
+    }
+    /// Sum the per second values of all rows of a metric into a "total" row per second label,
+    /// such as the total of all cpus per mode, or of all devices.
+    /// This is done once per metric per scrape, after all samples are processed.
+    pub fn create_total(
+        &mut self,
+        hostname: &str,
+        metric: &str,
+    )
+    {
+        let Some(rows) = self.hosts.get_mut(hostname).and_then(|metrics| metrics.get_mut(metric)) else { return };
+        let mut totals: BTreeMap<String, Statistic> = BTreeMap::new();
+        for ((_, label2), statistic) in rows.iter().filter(|((label, _), _)| label != "total")
+        {
+            // a total that includes a reset row misses the difference of that row, so it is a new baseline too.
+            totals.entry(label2.to_string())
+                .and_modify(|total| {
+                    total.per_second_value += statistic.per_second_value;
+                    total.reset |= statistic.reset;
+                    total.first_value |= statistic.reset;
+                })
+                .or_insert(Statistic {
+                    per_second_value: statistic.per_second_value,
+                    last_timestamp: statistic.last_timestamp,
+                    first_value: statistic.first_value,
+                    reset: statistic.reset,
+                    ..Default::default()
+                });
+        }
+        for (label2, total) in totals
+        {
+            rows.entry(("total".to_string(), label2))
+                .and_modify(|row| {
+                    row.per_second_value = total.per_second_value;
+                    row.last_timestamp = total.last_timestamp;
+                    row.first_value = total.first_value;
+                    row.reset = total.reset;
+                })
+                .or_insert(total);
+        }
+    }
+}
+
+/// The reason a sample could not be processed.
+#[derive(Debug)]
+pub enum StatisticError {
+    WrongValueType { metric: String, expected: &'static str },
+    MissingLabel { metric: String, label: &'static str },
+    UnparsableScrape,
+}
+
+impl fmt::Display for StatisticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self
+        {
+            StatisticError::WrongValueType { metric, expected } => write!(f, "{}: value type should be {}", metric, expected),
+            StatisticError::MissingLabel { metric, label } => write!(f, "{}: label {} is missing", metric, label),
+            StatisticError::UnparsableScrape => write!(f, "the scrape could not be parsed"),
+        }
+    }
+}
+
+impl std::error::Error for StatisticError {}
+
+/// The samples that could not be processed, counted per target and error.
+/// Every error is logged once per target, so a misbehaving exporter doesn't flood the output.
+#[derive(Debug, Default, Clone)]
+pub struct SampleErrors {
+    errors: BTreeMap<(String, String), u64>,
 }
 
+impl SampleErrors {
+    pub fn add(
+        &mut self,
+        hostname: &str,
+        error: &StatisticError,
+    )
+    {
+        let count = self.errors.entry((hostname.to_string(), error.to_string())).or_insert(0);
+        if *count == 0
+        {
+            warn!("{}: skipping sample: {}", hostname, error);
+        }
+        *count += 1;
+    }
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+    pub fn print_summary(&self) {
+        println!("{:30} {:>8} error", "target", "samples");
+        for ((hostname, error), count) in &self.errors
+        {
+            println!("{:30} {:8} {}", hostname, count, error);
+        }
+    }
+}
+
+/// Extra name/value pairs of a row.
+pub type Labels = Vec<(String, String)>;
+
+/// A row of a view: the values for a host, and a device or other label for the views that have one.
+/// The labels are extra name/value pairs for the machine readable output, such as the labels of the target in a file_sd file.
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub hostname: String,
+    pub timestamp: DateTime<Utc>,
+    pub label: String,
+    pub labels: Labels,
+    pub values: Vec<f64>,
+}
+
+/// The values of the rows shown during a run, per host and device (or other label), for the summary at the end.
+#[derive(Debug, Default, Clone)]
+pub struct Averages {
+    rows: BTreeMap<(String, String, Labels), AverageRow>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct AverageRow {
+    count: u64,
+    sum: Vec<f64>,
+    min: Vec<f64>,
+    max: Vec<f64>,
+}
+
+impl Averages {
+    pub fn add(
+        &mut self,
+        hostname: &str,
+        label: &str,
+        labels: &[(String, String)],
+        values: &[f64],
+    )
+    {
+        let row = self.rows.entry((hostname.to_string(), label.to_string(), labels.to_vec())).or_default();
+        if row.count == 0 || row.sum.len() != values.len()
+        {
+            *row = AverageRow { count: 0, sum: vec![0.; values.len()], min: values.to_vec(), max: values.to_vec() };
+        }
+        row.count += 1;
+        for (index, value) in values.iter().enumerate()
+        {
+            row.sum[index] += value;
+            row.min[index] = row.min[index].min(*value);
+            row.max[index] = row.max[index].max(*value);
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// The summary rows per host and label: the average, and with minmax the minimum and maximum, as (kind, hostname, label, labels, values).
+    pub fn summary(
+        &self,
+        minmax: bool,
+    ) -> Vec<(&'static str, &str, &str, &Labels, Vec<f64>)>
+    {
+        let mut summary = Vec::new();
+        for ((hostname, label, labels), row) in &self.rows
+        {
+            summary.push(("average", hostname.as_str(), label.as_str(), labels, row.sum.iter().map(|sum| sum / row.count as f64).collect()));
+            if minmax
+            {
+                summary.push(("min", hostname.as_str(), label.as_str(), labels, row.min.clone()));
+                summary.push(("max", hostname.as_str(), label.as_str(), labels, row.max.clone()));
+            }
+        }
+        summary
+    }
+    /// Print the `Average:` rows, and the `Min:` and `Max:` rows with minmax, with the row printer of the output.
+    pub fn print(
+        &self,
+        minmax: bool,
+        print_row: impl Fn(&str, &str, &str, &Labels, &[f64]),
+    )
+    {
+        let width = time_header().len();
+        for (kind, hostname, label, labels, values) in self.summary(minmax)
+        {
+            let time = match kind
+            {
+                "average" => "Average:",
+                "min" => "Min:",
+                _ => "Max:",
+            };
+            print_row(hostname, &format!("{:width$}", time), label, labels, &values);
+        }
+    }
+}
+
+/// The unparsed response of a prometheus endpoint, and the time it was fetched.
 #[derive(Debug, Default)]
+pub struct RawScrape {
+    pub timestamp: DateTime<Utc>,
+    pub data: String,
+}
+
+// the historical data is saved as json, which only allows strings as map keys.
+// the maps are therefore (de)serialized as a list of (key, value) pairs.
+mod map_as_list {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(
+        map: &BTreeMap<K, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+    pub fn deserialize<'de, K, V, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|list| list.into_iter().collect())
+    }
+}
+
+/// Write the rows of a category of the historical data to a csv file, with the key columns before the values.
+/// The names of the value columns are the field names of the details struct.
+fn write_csv<'a, T: Serialize + 'a>(
+    path: &str,
+    key_names: &[&str],
+    rows: impl Iterator<Item = (Vec<String>, &'a T)>,
+) -> Result<Option<String>>
+{
+    let mut rows = rows.peekable();
+    let Some((_, first)) = rows.peek() else { return Ok(None) };
+    // the csv writer only creates a header for a struct on its own, so the header is taken from a struct written to memory.
+    let mut header_writer = csv::Writer::from_writer(Vec::new());
+    header_writer.serialize(first)?;
+    let header_data = header_writer.into_inner().map_err(|error| anyhow!("{}", error))?;
+    let field_names = csv::Reader::from_reader(header_data.as_slice()).headers()?.clone();
+
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_path(path).with_context(|| format!("creating csv file {}", path))?;
+    writer.write_record(key_names.iter().copied().chain(field_names.iter()))?;
+    for (keys, details) in rows
+    {
+        writer.serialize((keys, details)).with_context(|| format!("writing csv file {}", path))?;
+    }
+    writer.flush()?;
+    Ok(Some(path.to_string()))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct HistoricalData {
+    #[serde(with = "map_as_list")]
     pub cpu_details: BTreeMap<(String, DateTime<Utc>), NodeCpuDetails>,
+    // the cpus separately: all details only when the history is saved or exported, and the busy percentage for the cpu heatmap.
+    // a history file saved before they were added doesn't have them.
+    #[serde(with = "map_as_list", default)]
+    pub per_cpu_details: BTreeMap<(String, DateTime<Utc>, String), NodeCpuDetails>,
+    #[serde(with = "map_as_list", default)]
+    pub per_cpu_busy: BTreeMap<(String, DateTime<Utc>, String), f64>,
+    #[serde(skip)]
+    per_cpu: PerCpuHistory,
+    #[serde(with = "map_as_list")]
     pub disk_details: BTreeMap<(String, DateTime<Utc>, String), NodeDiskDetails>,
+    #[serde(with = "map_as_list")]
     pub network_details: BTreeMap<(String, DateTime<Utc>, String), NodeNetworkDetails>,
+    #[serde(with = "map_as_list")]
     pub memory_details: BTreeMap<(String, DateTime<Utc>), NodeMemoryDetails>,
+    #[serde(with = "map_as_list")]
     pub yb_memory_details: BTreeMap<(String, DateTime<Utc>), YbMemoryDetails>,
+    #[serde(with = "map_as_list")]
     pub yb_io_details: BTreeMap<(String, DateTime<Utc>), YbIoDetails>,
+    #[serde(with = "map_as_list")]
     pub misc_details: BTreeMap<(String, DateTime<Utc>), NodeMiscDetails>,
+    #[serde(with = "map_as_list")]
     pub vmstat_details: BTreeMap<(String, DateTime<Utc>), NodeVmstatDetails>,
+    #[serde(with = "map_as_list", default)]
+    pub netstat_details: BTreeMap<(String, DateTime<Utc>), NodeNetstatDetails>,
+}
+
+/// What is kept of the cpus separately: a machine with many cpus would otherwise add all cpu details of every cpu every interval.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PerCpuHistory {
+    #[default]
+    None,
+    /// The busy percentage, for the cpu heatmap.
+    Busy,
+    /// All cpu details, for the saved history and the csv export, and the busy percentage.
+    Details,
 }
 
 impl HistoricalData {
-    pub fn new() -> Self {
-        Default::default()
+    pub fn new(
+        per_cpu: PerCpuHistory,
+    ) -> Self
+    {
+        Self { per_cpu, ..Default::default() }
+    }
+    /// Save the historical data as gzip compressed json, to be plotted later.
+    pub fn save(
+        &self,
+        path: &str,
+    ) -> Result<()>
+    {
+        let file = File::create(path).with_context(|| format!("creating history file {}", path))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, self).with_context(|| format!("writing history file {}", path))?;
+        encoder.finish()?;
+        Ok(())
+    }
+    pub fn load(
+        path: &str,
+    ) -> Result<Self>
+    {
+        let file = File::open(path).with_context(|| format!("opening history file {}", path))?;
+        serde_json::from_reader(BufReader::new(GzDecoder::new(file))).with_context(|| format!("reading history file {}", path))
+    }
+    /// Export the historical data as csv, one file per category, named prefix_cpu.csv, prefix_disk.csv and so on.
+    /// Every row has the hostname, the timestamp and, for per cpu, disk and network, the cpu or device, followed by the values.
+    /// Categories without data are skipped; the files that are written are returned.
+    pub fn export_csv(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<String>>
+    {
+        let timestamp = |timestamp: &DateTime<Utc>| timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let files = [
+            write_csv(&format!("{}_cpu.csv", prefix), &["hostname", "timestamp"], self.cpu_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+            write_csv(&format!("{}_per_cpu.csv", prefix), &["hostname", "timestamp", "cpu"], self.per_cpu_details.iter().map(|((hostname, time, cpu), details)| (vec![hostname.clone(), timestamp(time), cpu.clone()], details)))?,
+            write_csv(&format!("{}_disk.csv", prefix), &["hostname", "timestamp", "device"], self.disk_details.iter().map(|((hostname, time, device), details)| (vec![hostname.clone(), timestamp(time), device.clone()], details)))?,
+            write_csv(&format!("{}_network.csv", prefix), &["hostname", "timestamp", "device"], self.network_details.iter().map(|((hostname, time, device), details)| (vec![hostname.clone(), timestamp(time), device.clone()], details)))?,
+            write_csv(&format!("{}_memory.csv", prefix), &["hostname", "timestamp"], self.memory_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+            write_csv(&format!("{}_misc.csv", prefix), &["hostname", "timestamp"], self.misc_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+            write_csv(&format!("{}_netstat.csv", prefix), &["hostname", "timestamp"], self.netstat_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+            write_csv(&format!("{}_vmstat.csv", prefix), &["hostname", "timestamp"], self.vmstat_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+            write_csv(&format!("{}_yb_memory.csv", prefix), &["hostname", "timestamp"], self.yb_memory_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+            write_csv(&format!("{}_yb_io.csv", prefix), &["hostname", "timestamp"], self.yb_io_details.iter().map(|((hostname, time), details)| (vec![hostname.clone(), timestamp(time)], details)))?,
+        ];
+        Ok(files.into_iter().flatten().collect())
+    }
+    /// Only keep the data between start and end, and for the hosts in the list.
+    /// A host matches on the full "host:port:endpoint" name, or on "host" or "host:port".
+    pub fn retain(
+        &mut self,
+        start: Option<TimeBound>,
+        end: Option<TimeBound>,
+        hosts: Option<&Vec<&str>>,
+    )
+    {
+        let keep = |hostname: &String, timestamp: &DateTime<Utc>| -> bool {
+            !start.map(|start| start.is_after(*timestamp)).unwrap_or(false)
+            && !end.map(|end| end.is_before(*timestamp)).unwrap_or(false)
+            && hosts.map(|hosts| hosts.iter().any(|host| hostname == host || hostname.starts_with(&format!("{}:", host)))).unwrap_or(true)
+        };
+        self.cpu_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
+        self.per_cpu_details.retain(|(hostname, timestamp, _), _| keep(hostname, timestamp));
+        self.per_cpu_busy.retain(|(hostname, timestamp, _), _| keep(hostname, timestamp));
+        self.disk_details.retain(|(hostname, timestamp, _), _| keep(hostname, timestamp));
+        self.network_details.retain(|(hostname, timestamp, _), _| keep(hostname, timestamp));
+        self.memory_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
+        self.yb_memory_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
+        self.yb_io_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
+        self.misc_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
+        self.vmstat_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
+        self.netstat_details.retain(|(hostname, timestamp), _| keep(hostname, timestamp));
     }
     pub fn add(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
         self.add_node_cpu_statistics(statistics);
@@ -75,105 +593,130 @@ impl HistoricalData {
         self.add_yb_io_statistics(statistics);
         self.add_node_misc_statistics(statistics);
         self.add_node_vmstat_statistics(statistics);
+        self.add_node_netstat_statistics(statistics);
     }
     pub fn add_node_cpu_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_cpu_seconds_total" && !row.first_value )
+            if !statistics.rows(hostname, "node_cpu_seconds_total").any(|(_, row)| !row.first_value)
             {
-                let timestamp = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-                let user = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let nice = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "nice" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let system = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "system" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                continue;
+            }
+            // the total, and, if they are kept, every cpu that has a value, which excludes a cpu that was just brought online.
+            let cpus = statistics.rows(hostname, "node_cpu_seconds_total")
+                .filter(|((cpu, _), row)| self.per_cpu != PerCpuHistory::None && cpu != "total" && !row.first_value)
+                .map(|((cpu, _), _)| cpu.as_str())
+                .unique()
+                .collect::<Vec<_>>();
+            for cpu in std::iter::once("total").chain(cpus)
+            {
+                let timestamp = statistics.get(hostname, "node_cpu_seconds_total", cpu, "user").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+                let user = statistics.get(hostname, "node_cpu_seconds_total", cpu, "user").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let nice = statistics.get(hostname, "node_cpu_seconds_total", cpu, "nice").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let system = statistics.get(hostname, "node_cpu_seconds_total", cpu, "system").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_cpu_seconds iowait mode doesn't exist on Mac
-                let iowait = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "iowait" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let iowait = statistics.get(hostname, "node_cpu_seconds_total", cpu, "iowait").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_cpu_seconds steal mode doesn't exist on Mac
-                let steal = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "steal" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let steal = statistics.get(hostname, "node_cpu_seconds_total", cpu, "steal").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_cpu_seconds irq mode doesn't exist on Mac
-                let irq = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "irq" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let irq = statistics.get(hostname, "node_cpu_seconds_total", cpu, "irq").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_cpu_seconds softirq mode doesn't exist on Mac
-                let softirq = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "softirq" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let softirq = statistics.get(hostname, "node_cpu_seconds_total", cpu, "softirq").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_cpu_guest doesn't exist on Mac
-                let guest_user = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_guest_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let guest_user = statistics.get(hostname, "node_cpu_guest_seconds_total", cpu, "user").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_cpu_guest doesn't exist on Mac
-                let guest_nice = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_guest_seconds_total" && mode == "nice" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let idle = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "idle" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let guest_nice = statistics.get(hostname, "node_cpu_guest_seconds_total", cpu, "nice").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let idle = statistics.get(hostname, "node_cpu_seconds_total", cpu, "idle").map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_schedstat_running doesn't exist on Mac
-                let schedstat_runtime = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_schedstat_running_seconds_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let schedstat_runtime = statistics.first_with(hostname, "node_schedstat_running_seconds_total", cpu).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_schedstat_waiting doesn't exist on Mac
-                let schedstat_wait = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_schedstat_waiting_seconds_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                self.cpu_details.entry((hostname.to_string(), timestamp)).or_insert(
-                    NodeCpuDetails {
-                        user,
-                        nice,
-                        system,
-                        iowait,
-                        steal,
-                        irq,
-                        softirq,
-                        guest_user,
-                        guest_nice,
-                        idle,
-                        schedstat_runtime,
-                        schedstat_wait,
+                let schedstat_wait = statistics.first_with(hostname, "node_schedstat_waiting_seconds_total", cpu).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let details = NodeCpuDetails {
+                    user,
+                    nice,
+                    system,
+                    iowait,
+                    steal,
+                    irq,
+                    softirq,
+                    guest_user,
+                    guest_nice,
+                    idle,
+                    schedstat_runtime,
+                    schedstat_wait,
+                };
+                if cpu == "total"
+                {
+                    self.cpu_details.entry((hostname.to_string(), timestamp)).or_insert(details);
+                }
+                else
+                {
+                    if let Some(busy) = details.busy_percent()
+                    {
+                        self.per_cpu_busy.entry((hostname.to_string(), timestamp, cpu.to_string())).or_insert(busy);
                     }
-                );
+                    if self.per_cpu == PerCpuHistory::Details
+                    {
+                        self.per_cpu_details.entry((hostname.to_string(), timestamp, cpu.to_string())).or_insert(details);
+                    }
+                }
             }
         }
     }
     pub fn add_node_network_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_network_receive_packets_total" && !row.first_value )
+            if statistics.rows(hostname, "node_network_receive_packets_total").any(|(_, row)| !row.first_value)
             {
-                for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_network_receive_packets_total").map(|((_, _, device, _), _)| device)
+                // a device with a counter that was reset has no values for this interval, instead of zeros.
+                for current_device in statistics.rows(hostname, "node_network_receive_packets_total").map(|((device, _), _)| device).filter(|device| !statistics.new_baseline_with(hostname, "node_network_", device))
                 {
-                    let timestamp = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_packets_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+                    let timestamp = statistics.first_with(hostname, "node_network_receive_packets_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
 
-                    let receive_packets = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_packets_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let transmit_packets = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_packets_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let receive_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let transmit_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                    let receive_packets = statistics.first_with(hostname, "node_network_receive_packets_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let transmit_packets = statistics.first_with(hostname, "node_network_transmit_packets_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let receive_bytes = statistics.first_with(hostname, "node_network_receive_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let transmit_bytes = statistics.first_with(hostname, "node_network_transmit_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // does not exist on OSX
-                    let receive_compressed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_compressed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let receive_compressed = statistics.first_with(hostname, "node_network_receive_compressed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // does not exist on OSX
-                    let transmit_compressed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_compressed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let receive_multicast = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_multicast_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let receive_errs = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_errs_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let transmit_errs = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_errs_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let transmit_colls = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_colls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let receive_drop = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_drop_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                    let transmit_compressed = statistics.first_with(hostname, "node_network_transmit_compressed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let receive_multicast = statistics.first_with(hostname, "node_network_receive_multicast_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let receive_errs = statistics.first_with(hostname, "node_network_receive_errs_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let transmit_errs = statistics.first_with(hostname, "node_network_transmit_errs_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let transmit_colls = statistics.first_with(hostname, "node_network_transmit_colls_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let receive_drop = statistics.first_with(hostname, "node_network_receive_drop_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // does not exist on OSX
-                    let transmit_drop = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_drop_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let transmit_drop = statistics.first_with(hostname, "node_network_transmit_drop_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // does not exist on OSX
-                    let transmit_carrier = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_carrier_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let transmit_carrier = statistics.first_with(hostname, "node_network_transmit_carrier_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // does not exist on OSX
-                    let receive_fifo = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_fifo_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let receive_fifo = statistics.first_with(hostname, "node_network_receive_fifo_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // does not exist on OSX
-                    let transmit_fifo = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_fifo_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-
-                    let sockstat_sockets = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_sockets_used" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_tcp_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_TCP_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_udp_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_UDP_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_raw_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_RAW_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_frag_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_FRAG_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_tcp_tw = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_TCP_tw" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_tcp6_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_TCP6_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_udp6_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_UDP6_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_raw6_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_RAW6_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                    let sockstat_frag6_inuse = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_FRAG6_inuse" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-
-                    let softnet_dropped = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_softnet_dropped_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let softnet_processed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_softnet_processed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let softnet_times_squeezed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_sockstat_times_squeezed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let transmit_fifo = statistics.first_with(hostname, "node_network_transmit_fifo_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+
+                    let sockstat_sockets = statistics.first_with(hostname, "node_sockstat_sockets_used", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_tcp_inuse = statistics.first_with(hostname, "node_sockstat_TCP_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_udp_inuse = statistics.first_with(hostname, "node_sockstat_UDP_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_raw_inuse = statistics.first_with(hostname, "node_sockstat_RAW_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_frag_inuse = statistics.first_with(hostname, "node_sockstat_FRAG_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_tcp_tw = statistics.first_with(hostname, "node_sockstat_TCP_tw", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_tcp6_inuse = statistics.first_with(hostname, "node_sockstat_TCP6_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_udp6_inuse = statistics.first_with(hostname, "node_sockstat_UDP6_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_raw6_inuse = statistics.first_with(hostname, "node_sockstat_RAW6_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+                    let sockstat_frag6_inuse = statistics.first_with(hostname, "node_sockstat_FRAG6_inuse", current_device).map(|statistic| statistic.last_value).unwrap_or_default();
+
+                    let softnet_dropped = statistics.first_with(hostname, "node_softnet_dropped_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let softnet_processed = statistics.first_with(hostname, "node_softnet_processed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let softnet_times_squeezed = statistics.first_with(hostname, "node_sockstat_times_squeezed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
 
                     self.network_details.entry((hostname.to_string(), timestamp, current_device.to_string())).or_insert(
                         NodeNetworkDetails {
@@ -213,44 +756,45 @@ impl HistoricalData {
     }
     pub fn add_node_disk_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_disk_read_bytes_total" && !row.first_value )
+            if statistics.rows(hostname, "node_disk_read_bytes_total").any(|(_, row)| !row.first_value)
             {
-                for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_disk_read_bytes_total").map(|((_, _, device, _), _)| device)
+                // a device with a counter that was reset has no values for this interval, instead of zeros.
+                for current_device in statistics.rows(hostname, "node_disk_read_bytes_total").map(|((device, _), _)| device).filter(|device| !statistics.new_baseline_with(hostname, "node_disk_", device))
                 {
-                    let reads_completed_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let reads_bytes_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                    let reads_completed_s = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let reads_bytes_s = statistics.first_with(hostname, "node_disk_read_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // node_disk_reads_merged doesn't exist on Mac
-                    let reads_merged_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_merged_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let reads_time_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                    let reads_merged_s = statistics.first_with(hostname, "node_disk_reads_merged_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let reads_time_s = statistics.first_with(hostname, "node_disk_read_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     let reads_avg_latency_s = if (reads_time_s / reads_completed_s).is_nan() { 0. } else { reads_time_s / reads_completed_s };
 
-                    let writes_completed_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_writes_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                    let writes_bytes_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_written_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                    let writes_completed_s = statistics.first_with(hostname, "node_disk_writes_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let writes_bytes_s = statistics.first_with(hostname, "node_disk_written_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     // node_disk_writes_merged doesn't exist on Mac
-                    let writes_merged_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_writes_merged_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let writes_time_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_write_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                    let writes_merged_s = statistics.first_with(hostname, "node_disk_writes_merged_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let writes_time_s = statistics.first_with(hostname, "node_disk_write_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     let writes_avg_latency_s = if (writes_time_s / writes_completed_s).is_nan() { 0. } else { writes_time_s / writes_completed_s };
 
                     // discards are not available with either centos 7 or an earlier node_exporter version
-                    let discards_completed_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_discards_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let discards_sectors_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_discarded_sectors_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let discards_merged_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_discards_merged_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let discards_time_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_discard_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let discards_completed_s = statistics.first_with(hostname, "node_disk_discards_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let discards_sectors_s = statistics.first_with(hostname, "node_disk_discarded_sectors_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let discards_merged_s = statistics.first_with(hostname, "node_disk_discards_merged_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let discards_time_s = statistics.first_with(hostname, "node_disk_discard_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                     let discards_avg_latency = if (discards_time_s / discards_completed_s).is_nan() { 0. } else { discards_time_s / discards_completed_s };
 
                     // xfs is per partition, disks are per disk
                     // but both share 'total'!
-                    let xfs_read_calls_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_xfs_read_calls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let xfs_write_calls_s = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_xfs_write_calls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                    let xfs_read_calls_s = statistics.first_with(hostname, "node_xfs_read_calls_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let xfs_write_calls_s = statistics.first_with(hostname, "node_xfs_write_calls_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
 
                     // node_disk_io_time_weighted doesn't exist on Mac
-                    let queue_size = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_io_time_weighted_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                    let timestamp = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+                    let queue_size = statistics.first_with(hostname, "node_disk_io_time_weighted_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                    let timestamp = statistics.first_with(hostname, "node_disk_read_bytes_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
 
                     self.disk_details.entry((hostname.to_string(), timestamp, current_device.to_string())).or_insert(
                         NodeDiskDetails {
@@ -277,65 +821,65 @@ impl HistoricalData {
     }
     pub fn add_node_memory_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes" )
+            if statistics.contains_metric(hostname, "node_memory_MemFree_bytes")
             {
-                let timestamp = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-
-                let active_anon = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Active_anon_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let active = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Active_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let active_file = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Active_file_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let anonhugepages = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_AnonHugePages_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let anonpages = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_AnonPages_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let bounce = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Bounce_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let buffers = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Buffers_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let cached = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Cached_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let commitlimit = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_CommitLimit_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let committed_as = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Committed_AS_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let directmap2m = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_DirectMap2M_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                let directmap4k = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_DirectMap4k_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                let dirty = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Dirty_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let filehugepages = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_FileHugePages_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let filepmdmapped = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_FilePmdMapped_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let hardwarecorrupted = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_HardwareCorrupted_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-                let hugepages_free = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_HugePages_Free").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let hugepages_rsvd = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_HugePages_Rsvd").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let hugepages_surp = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_HugePages_Surp").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let hugepages_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_HugePages_Total").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let hugepagesize = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Hugepagesize_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let hugetlb = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Hugetlb_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let inactive_anon = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Inactive_anon_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let inactive = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Inactive_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let inactive_file = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Inactive_file_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let kreclaimable = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_KReclaimable_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let kernelstack = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_KernelStack_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mapped = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Mapped_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let memavailable = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemAvailable_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let memfree = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let memtotal = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mlocked = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Mlocked_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let nfs_unstable = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_NFS_Unstable_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let pagetables = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_PageTables_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let percpu = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Percpu_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let sreclaimable = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SReclaimable_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let sunreclaim = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SUnreclaim_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let shmemhugepages = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_ShmemHugePages_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let shmempmdmapped = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_ShmemPmdMapped_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
-                let shmem = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Shmem_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let slab = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Slab_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let swapcached = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapCached_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let swapfree = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let swaptotal = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let unevictable = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Unevictable_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let vmallocchunk = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_VmallocChunk_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let vmalloctotal = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_VmallocTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let vmallocused = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_VmallocUsed_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let writebacktmp = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_WritebackTmp_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let writeback = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Writeback_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
+                let timestamp = statistics.first(hostname, "node_memory_MemFree_bytes").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+
+                let active_anon = statistics.first(hostname, "node_memory_Active_anon_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let active = statistics.first(hostname, "node_memory_Active_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let active_file = statistics.first(hostname, "node_memory_Active_file_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let anonhugepages = statistics.first(hostname, "node_memory_AnonHugePages_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let anonpages = statistics.first(hostname, "node_memory_AnonPages_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let bounce = statistics.first(hostname, "node_memory_Bounce_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let buffers = statistics.first(hostname, "node_memory_Buffers_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let cached = statistics.first(hostname, "node_memory_Cached_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let commitlimit = statistics.first(hostname, "node_memory_CommitLimit_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let committed_as = statistics.first(hostname, "node_memory_Committed_AS_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let directmap2m = statistics.first(hostname, "node_memory_DirectMap2M_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let directmap4k = statistics.first(hostname, "node_memory_DirectMap4k_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let dirty = statistics.first(hostname, "node_memory_Dirty_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let filehugepages = statistics.first(hostname, "node_memory_FileHugePages_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let filepmdmapped = statistics.first(hostname, "node_memory_FilePmdMapped_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let hardwarecorrupted = statistics.first(hostname, "node_memory_HardwareCorrupted_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let hugepages_free = statistics.first(hostname, "node_memory_HugePages_Free").map(|statistic| statistic.last_value).unwrap_or_default();
+                let hugepages_rsvd = statistics.first(hostname, "node_memory_HugePages_Rsvd").map(|statistic| statistic.last_value).unwrap_or_default();
+                let hugepages_surp = statistics.first(hostname, "node_memory_HugePages_Surp").map(|statistic| statistic.last_value).unwrap_or_default();
+                let hugepages_total = statistics.first(hostname, "node_memory_HugePages_Total").map(|statistic| statistic.last_value).unwrap_or_default();
+                let hugepagesize = statistics.first(hostname, "node_memory_Hugepagesize_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let hugetlb = statistics.first(hostname, "node_memory_Hugetlb_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let inactive_anon = statistics.first(hostname, "node_memory_Inactive_anon_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let inactive = statistics.first(hostname, "node_memory_Inactive_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let inactive_file = statistics.first(hostname, "node_memory_Inactive_file_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let kreclaimable = statistics.first(hostname, "node_memory_KReclaimable_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let kernelstack = statistics.first(hostname, "node_memory_KernelStack_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mapped = statistics.first(hostname, "node_memory_Mapped_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let memavailable = statistics.first(hostname, "node_memory_MemAvailable_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let memfree = statistics.first(hostname, "node_memory_MemFree_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let memtotal = statistics.first(hostname, "node_memory_MemTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mlocked = statistics.first(hostname, "node_memory_Mlocked_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let nfs_unstable = statistics.first(hostname, "node_memory_NFS_Unstable_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let pagetables = statistics.first(hostname, "node_memory_PageTables_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let percpu = statistics.first(hostname, "node_memory_Percpu_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let sreclaimable = statistics.first(hostname, "node_memory_SReclaimable_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let sunreclaim = statistics.first(hostname, "node_memory_SUnreclaim_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let shmemhugepages = statistics.first(hostname, "node_memory_ShmemHugePages_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let shmempmdmapped = statistics.first(hostname, "node_memory_ShmemPmdMapped_bytes").map(|statistic| statistic.last_value).unwrap_or_default(); // centos 7 / old node exporter
+                let shmem = statistics.first(hostname, "node_memory_Shmem_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let slab = statistics.first(hostname, "node_memory_Slab_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let swapcached = statistics.first(hostname, "node_memory_SwapCached_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let swapfree = statistics.first(hostname, "node_memory_SwapFree_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let swaptotal = statistics.first(hostname, "node_memory_SwapTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let unevictable = statistics.first(hostname, "node_memory_Unevictable_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let vmallocchunk = statistics.first(hostname, "node_memory_VmallocChunk_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let vmalloctotal = statistics.first(hostname, "node_memory_VmallocTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let vmallocused = statistics.first(hostname, "node_memory_VmallocUsed_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let writebacktmp = statistics.first(hostname, "node_memory_WritebackTmp_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+                let writeback = statistics.first(hostname, "node_memory_Writeback_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
                 self.memory_details.entry((hostname.to_string(), timestamp)).or_insert(
                     NodeMemoryDetails{
                         active_anon,
@@ -395,36 +939,36 @@ impl HistoricalData {
     }
     pub fn add_yb_memory_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_heap_size" && metric_type == "server" )
+            if statistics.first_with(hostname, "generic_heap_size", "server").is_some()
             {
-                let generic_heap = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_heap_size" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let generic_allocated = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_current_allocated_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let tcmalloc_pageheap_free = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_pageheap_free_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let tcmalloc_max_total_thread_cache = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_max_total_thread_cache_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let tcmalloc_current_total_thread_cache = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_current_total_thread_cache_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let tcmalloc_pageheap_unmapped = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_pageheap_unmapped_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_call = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Call" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_read_buffer = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_compressed_read_buffer = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Compressed_Read_Buffer" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_tablets = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Tablets" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master does not have tablets
-                let mem_tracker_log_cache = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_log_cache" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_blockbasedtable = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_BlockBasedTable" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_compressed_read_buffer_receive = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Compressed_Read_Buffer_Receive" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_read_buffer_inbound_rpc_sending = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Inbound_RPC_Sending" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
-                let mem_tracker_read_buffer_inbound_rpc_receive = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Inbound_RPC_Receive" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
-                let mem_tracker_read_buffer_inbound_rpc_reading = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Inbound_RPC_Reading" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
-                let mem_tracker_read_buffer_outbound_rpc_queueing = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Queueing" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let mem_tracker_read_buffer_outbound_rpc_receive = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Receive" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master follower does not have this
-                let mem_tracker_read_buffer_outbound_rpc_sending = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Sending" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master follower does not have this
-                let mem_tracker_read_buffer_outbound_rpc_reading = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Reading" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master follower does not have this
+                let generic_heap = statistics.first_with(hostname, "generic_heap_size", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let generic_allocated = statistics.first_with(hostname, "generic_current_allocated_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let tcmalloc_pageheap_free = statistics.first_with(hostname, "tcmalloc_pageheap_free_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let tcmalloc_max_total_thread_cache = statistics.first_with(hostname, "tcmalloc_max_total_thread_cache_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let tcmalloc_current_total_thread_cache = statistics.first_with(hostname, "tcmalloc_current_total_thread_cache_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let tcmalloc_pageheap_unmapped = statistics.first_with(hostname, "tcmalloc_pageheap_unmapped_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker = statistics.first_with(hostname, "mem_tracker", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_call = statistics.first_with(hostname, "mem_tracker_Call", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_read_buffer = statistics.first_with(hostname, "mem_tracker_Read_Buffer", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_compressed_read_buffer = statistics.first_with(hostname, "mem_tracker_Compressed_Read_Buffer", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_tablets = statistics.first_with(hostname, "mem_tracker_Tablets", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master does not have tablets
+                let mem_tracker_log_cache = statistics.first_with(hostname, "mem_tracker_log_cache", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_blockbasedtable = statistics.first_with(hostname, "mem_tracker_BlockBasedTable", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_compressed_read_buffer_receive = statistics.first_with(hostname, "mem_tracker_Compressed_Read_Buffer_Receive", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_read_buffer_inbound_rpc_sending = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Inbound_RPC_Sending", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
+                let mem_tracker_read_buffer_inbound_rpc_receive = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Inbound_RPC_Receive", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
+                let mem_tracker_read_buffer_inbound_rpc_reading = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Inbound_RPC_Reading", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
+                let mem_tracker_read_buffer_outbound_rpc_queueing = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Queueing", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+                let mem_tracker_read_buffer_outbound_rpc_receive = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Receive", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master follower does not have this
+                let mem_tracker_read_buffer_outbound_rpc_sending = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Sending", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master follower does not have this
+                let mem_tracker_read_buffer_outbound_rpc_reading = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Reading", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master follower does not have this
                 let mem_tracker_independent_allocs = mem_tracker_compressed_read_buffer_receive + mem_tracker_read_buffer_inbound_rpc_sending + mem_tracker_read_buffer_inbound_rpc_receive + mem_tracker_read_buffer_inbound_rpc_reading + mem_tracker_read_buffer_outbound_rpc_queueing + mem_tracker_read_buffer_outbound_rpc_receive + mem_tracker_read_buffer_outbound_rpc_sending + mem_tracker_read_buffer_outbound_rpc_reading;
-                let timestamp = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_heap_size" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+                let timestamp = statistics.first_with(hostname, "generic_heap_size", "server").map(|statistic| statistic.last_timestamp).unwrap_or_default();
                 self.yb_memory_details.entry((hostname.to_string(), timestamp)).or_insert(
                     YbMemoryDetails {
                         generic_heap,
@@ -448,39 +992,39 @@ impl HistoricalData {
     }
     pub fn add_yb_io_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, metric_type, _), row)| host == hostname && metric == "glog_info_messages" && metric_type == "server" && !row.first_value )
+            if statistics.rows(hostname, "glog_info_messages").any(|((metric_type, _), row)| metric_type == "server" && !row.first_value)
             {
-                let glog_info_messages = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_info_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let glog_warning_messages = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_warning_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default(); // statistic does not exist if no warnings have been generated
-                let glog_error_messages = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_error_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default(); // probably the same for error messages
-                let guaranteed_last_timestamp = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_info_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-
-                let log_bytes_logged = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_bytes_logged" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let log_reader_bytes_read = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_reader_bytes_read" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let log_sync_latency_count = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_sync_latency_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let log_sync_latency_sum = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_sync_latency_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let log_append_latency_count = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_append_latency_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let log_append_latency_sum = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_append_latency_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let log_cache_disk_reads = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_cache_disk_reads" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_flush_write_bytes = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_flush_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let intentsdb_rocksdb_flush_write_bytes = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_flush_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_compact_read_bytes = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_compact_read_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let intentsdb_rocksdb_compact_read_bytes = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_compact_read_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_compact_write_bytes = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_compact_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let intentsdb_rocksdb_compact_write_bytes = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_compact_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_write_raw_block_micros_count = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_write_raw_block_micros_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_write_raw_block_micros_sum = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_write_raw_block_micros_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_sst_read_micros_count = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_sst_read_micros_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_sst_read_micros_sum = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_sst_read_micros_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let intentsdb_rocksdb_block_cache_hit = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_block_cache_hit" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let intentsdb_rocksdb_block_cache_miss = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_block_cache_miss" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_block_cache_hit = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_block_cache_hit" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-                let rocksdb_block_cache_miss = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_block_cache_miss" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
+                let glog_info_messages = statistics.first_with(hostname, "glog_info_messages", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let glog_warning_messages = statistics.first_with(hostname, "glog_warning_messages", "server").map(|statistic| statistic.per_second_value).unwrap_or_default(); // statistic does not exist if no warnings have been generated
+                let glog_error_messages = statistics.first_with(hostname, "glog_error_messages", "server").map(|statistic| statistic.per_second_value).unwrap_or_default(); // probably the same for error messages
+                let guaranteed_last_timestamp = statistics.first_with(hostname, "glog_info_messages", "server").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+
+                let log_bytes_logged = statistics.rows(hostname, "log_bytes_logged").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let log_reader_bytes_read = statistics.rows(hostname, "log_reader_bytes_read").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let log_sync_latency_count = statistics.rows(hostname, "log_sync_latency_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let log_sync_latency_sum = statistics.rows(hostname, "log_sync_latency_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let log_append_latency_count = statistics.rows(hostname, "log_append_latency_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let log_append_latency_sum = statistics.rows(hostname, "log_append_latency_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let log_cache_disk_reads = statistics.rows(hostname, "log_cache_disk_reads").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_flush_write_bytes = statistics.rows(hostname, "rocksdb_flush_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let intentsdb_rocksdb_flush_write_bytes = statistics.rows(hostname, "intentsdb_rocksdb_flush_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_compact_read_bytes = statistics.rows(hostname, "rocksdb_compact_read_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let intentsdb_rocksdb_compact_read_bytes = statistics.rows(hostname, "intentsdb_rocksdb_compact_read_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_compact_write_bytes = statistics.rows(hostname, "rocksdb_compact_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let intentsdb_rocksdb_compact_write_bytes = statistics.rows(hostname, "intentsdb_rocksdb_compact_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_write_raw_block_micros_count = statistics.rows(hostname, "rocksdb_write_raw_block_micros_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_write_raw_block_micros_sum = statistics.rows(hostname, "rocksdb_write_raw_block_micros_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_sst_read_micros_count = statistics.rows(hostname, "rocksdb_sst_read_micros_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_sst_read_micros_sum = statistics.rows(hostname, "rocksdb_sst_read_micros_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let intentsdb_rocksdb_block_cache_hit = statistics.rows(hostname, "intentsdb_rocksdb_block_cache_hit").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let intentsdb_rocksdb_block_cache_miss = statistics.rows(hostname, "intentsdb_rocksdb_block_cache_miss").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_block_cache_hit = statistics.rows(hostname, "rocksdb_block_cache_hit").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+                let rocksdb_block_cache_miss = statistics.rows(hostname, "rocksdb_block_cache_miss").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
                 self.yb_io_details.entry((hostname.to_string(), guaranteed_last_timestamp)).or_insert(
                     YbIoDetails {
                         glog_info_messages,
@@ -514,27 +1058,27 @@ impl HistoricalData {
     }
     pub fn add_node_misc_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_load1" && !row.first_value )
+            if statistics.rows(hostname, "node_load1").any(|(_, row)| !row.first_value)
             {
-                let timestamp = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load1").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-
-                let some_cpu = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_cpu_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let some_io = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_io_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let full_io = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_io_stalled_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let some_mem = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_memory_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let full_mem = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_memory_stalled_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let load_1 = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load1").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let load_5 = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load5").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let load_15 = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load15").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let interrupts = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_intr_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let context_switches = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_context_switches_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let processes_running = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_procs_running").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-                let processes_blocked = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_procs_blocked").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
+                let timestamp = statistics.first(hostname, "node_load1").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+
+                let some_cpu = statistics.first(hostname, "node_pressure_cpu_waiting_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let some_io = statistics.first(hostname, "node_pressure_io_waiting_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let full_io = statistics.first(hostname, "node_pressure_io_stalled_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let some_mem = statistics.first(hostname, "node_pressure_memory_waiting_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let full_mem = statistics.first(hostname, "node_pressure_memory_stalled_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let load_1 = statistics.first(hostname, "node_load1").map(|statistic| statistic.last_value).unwrap_or_default();
+                let load_5 = statistics.first(hostname, "node_load5").map(|statistic| statistic.last_value).unwrap_or_default();
+                let load_15 = statistics.first(hostname, "node_load15").map(|statistic| statistic.last_value).unwrap_or_default();
+                let interrupts = statistics.first(hostname, "node_intr_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let context_switches = statistics.first(hostname, "node_context_switches_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let processes_running = statistics.first(hostname, "node_procs_running").map(|statistic| statistic.last_value).unwrap_or_default();
+                let processes_blocked = statistics.first(hostname, "node_procs_blocked").map(|statistic| statistic.last_value).unwrap_or_default();
                 self.misc_details.entry((hostname.to_string(), timestamp)).or_insert(
                     NodeMiscDetails {
                         some_cpu,
@@ -556,22 +1100,22 @@ impl HistoricalData {
     }
     pub fn add_node_vmstat_statistics(
         &mut self,
-        statistics: &BTreeMap<(String, String, String, String), Statistic>,
+        statistics: &Statistics,
     )
     {
-        for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+        for hostname in statistics.hosts()
         {
-            if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_vmstat_pgpgin" && !row.first_value )
+            if statistics.rows(hostname, "node_vmstat_pgpgin").any(|(_, row)| !row.first_value)
             {
-                let timestamp = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgpgin").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-
-                let pages_swap_in = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpin").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let pages_swap_out = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpout").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let pages_page_in = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgpgin").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let pages_page_out = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgpgout").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let pages_minor_pagefault = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgfault").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let pages_major_pagefault = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgmajfault").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let oom_kill = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_oom_kill").map(|((_, _, _, _), statistic)| statistic.delta_value).unwrap();
+                let timestamp = statistics.first(hostname, "node_vmstat_pgpgin").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+
+                let pages_swap_in = statistics.first(hostname, "node_vmstat_pswpin").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let pages_swap_out = statistics.first(hostname, "node_vmstat_pswpout").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let pages_page_in = statistics.first(hostname, "node_vmstat_pgpgin").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let pages_page_out = statistics.first(hostname, "node_vmstat_pgpgout").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let pages_minor_pagefault = statistics.first(hostname, "node_vmstat_pgfault").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let pages_major_pagefault = statistics.first(hostname, "node_vmstat_pgmajfault").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let oom_kill = statistics.first(hostname, "node_vmstat_oom_kill").map(|statistic| statistic.delta_value).unwrap_or_default();
                 self.vmstat_details.entry((hostname.to_string(), timestamp)).or_insert(
                     NodeVmstatDetails {
                         pages_swap_in,
@@ -586,88 +1130,206 @@ impl HistoricalData {
             }
         }
     }
-}
-
-pub async fn read_node_exporter_into_map(
-    hosts: &Vec<&str>,
-    ports: &Vec<&str>,
-    parallel: usize,
-) -> HashMap<String, Scrape>
-{
-    let pool = rayon::ThreadPoolBuilder::new().num_threads(parallel).build().unwrap();
-    let (tx, rx) = channel();
-    pool.scope(move |s| {
-        for host in hosts {
-            for port in ports {
-                let endpoints = vec!["metrics", "prometheus-metrics"];
-                for endpoint in endpoints {
-                    let tx = tx.clone();
-                    s.spawn(move |_| {
-                        let node_exporter_values = read_node_exporter(host, port, endpoint);
-                        tx.send((format!("{}:{}:{}", host, port, endpoint), node_exporter_values)).expect("error sending data via tx (node_exporter)");
-                    });
-                }
+    pub fn add_node_netstat_statistics(
+        &mut self,
+        statistics: &Statistics,
+    )
+    {
+        for hostname in statistics.hosts()
+        {
+            if statistics.rows(hostname, "node_netstat_TcpExt_ListenOverflows").any(|(_, row)| !row.first_value)
+            {
+                let timestamp = statistics.first(hostname, "node_netstat_TcpExt_ListenOverflows").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+                // most of the abort counters are not exposed by node_exporter by default.
+                let listen_overflows = statistics.first(hostname, "node_netstat_TcpExt_ListenOverflows").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let listen_drops = statistics.first(hostname, "node_netstat_TcpExt_ListenDrops").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let syncookies_sent = statistics.first(hostname, "node_netstat_TcpExt_SyncookiesSent").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let syncookies_recv = statistics.first(hostname, "node_netstat_TcpExt_SyncookiesRecv").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let syncookies_failed = statistics.first(hostname, "node_netstat_TcpExt_SyncookiesFailed").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let abort_on_data = statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnData").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let abort_on_close = statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnClose").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let abort_on_memory = statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnMemory").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let abort_on_timeout = statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnTimeout").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let abort_on_linger = statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnLinger").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let abort_failed = statistics.first(hostname, "node_netstat_TcpExt_TCPAbortFailed").map(|statistic| statistic.per_second_value).unwrap_or_default();
+                self.netstat_details.entry((hostname.to_string(), timestamp)).or_insert(
+                    NodeNetstatDetails {
+                        listen_overflows,
+                        listen_drops,
+                        syncookies_sent,
+                        syncookies_recv,
+                        syncookies_failed,
+                        abort_on_data,
+                        abort_on_close,
+                        abort_on_memory,
+                        abort_on_timeout,
+                        abort_on_linger,
+                        abort_failed,
+                    }
+                );
             }
         }
-    });
-    let mut map_exporter_values: HashMap<String, Scrape> = HashMap::new();
-    for (hostname_port, node_exporter_values) in rx {
-        map_exporter_values.insert( hostname_port, node_exporter_values);
     }
-    map_exporter_values
 }
 
-pub fn read_node_exporter(
-    host: &str,
-    port: &str,
-    path: &str,
-) -> Scrape
-{
-    let response = if let Ok(data_from_http) = reqwest::blocking::Client::builder()
-        .connect_timeout(Duration::from_millis(200))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .unwrap()
-        .get(format!("http://{}:{}/{}", host, port, path))
-        .send()
+/// How the endpoints are scraped: TLS and authentication.
+#[derive(Debug, Clone, Default)]
+pub struct ScrapeOptions {
+    pub ca_certificate: Option<reqwest::Certificate>,
+    pub insecure_skip_verify: bool,
+    pub identity: Option<reqwest::Identity>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub bearer_token: Option<String>,
+}
+
+impl ScrapeOptions {
+    /// An async client with the TLS settings applied.
+    pub fn async_client(
+        &self,
+    ) -> reqwest::Result<reqwest::Client>
     {
-        if ! &data_from_http.status().is_success()
+        let mut client_builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_millis(200))
+            .tcp_keepalive(Duration::from_secs(60));
+        // the certificates are verified against the system roots, plus the CA certificate if given.
+        if let Some(ca_certificate) = &self.ca_certificate
         {
-            debug!("Non success response: {}:{}/{} = {}", host, port, path, &data_from_http.status());
+            client_builder = client_builder.add_root_certificate(ca_certificate.clone());
         }
-        else
+        if self.insecure_skip_verify
+        {
+            client_builder = client_builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(identity) = &self.identity
         {
-            debug!("Success response: {}:{}/{} = {}", host, port, path, &data_from_http.status());
+            client_builder = client_builder.identity(identity.clone());
         }
-        data_from_http.text().unwrap()
+        client_builder.build()
     }
-    else
+    /// Add the basic authentication or bearer token to an async request.
+    pub fn authenticate(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder
     {
-        debug!("Non-Ok success response: {}:{}/{}", host, port, path);
-        String::new()
-    };
-    parse_node_exporter(response)
+        if let Some(username) = &self.username
+        {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+        if let Some(bearer_token) = &self.bearer_token
+        {
+            request = request.bearer_auth(bearer_token);
+        }
+        request
+    }
+}
+
+/// Parse a duration in seconds, which can be fractional (0.5), or with a unit: 500ms, 2s, 10m, 1h.
+pub fn parse_duration(
+    duration: &str,
+) -> Result<Duration>
+{
+    let (number, unit) = [("ms", 0.001), ("s", 1.), ("m", 60.), ("h", 3600.)].iter()
+        .find_map(|(suffix, unit)| duration.strip_suffix(suffix).map(|number| (number, *unit)))
+        .unwrap_or((duration, 1.));
+    let seconds = number.trim().parse::<f64>().map(|number| number * unit).ok().filter(|seconds| seconds.is_finite() && *seconds > 0.);
+    seconds.map(Duration::from_secs_f64).ok_or_else(|| anyhow!("invalid duration: {} (use seconds, such as 0.5 or 2, or a unit: 500ms, 2s, 10m, 1h)", duration))
+}
+
+// with a sub-second interval, the time column shows milliseconds, otherwise the rows of the intervals within a second look alike.
+static SUBSECOND_TIME: AtomicBool = AtomicBool::new(false);
+
+pub fn set_subsecond_time(
+    subsecond: bool,
+)
+{
+    SUBSECOND_TIME.store(subsecond, Ordering::Relaxed);
+}
+
+pub fn subsecond_time() -> bool {
+    SUBSECOND_TIME.load(Ordering::Relaxed)
 }
 
+/// The time column of the output.
+pub fn format_time(
+    timestamp: DateTime<Utc>,
+) -> String
+{
+    if subsecond_time() { timestamp.format("%H:%M:%S%.3f").to_string() } else { timestamp.format("%H:%M:%S").to_string() }
+}
+
+/// The header of the time column, as wide as the time column.
+pub fn time_header() -> &'static str {
+    if subsecond_time() { "time        " } else { "time" }
+}
+
+/// Read a secret from a file or from an environment variable, so it doesn't have to be on the command line.
+pub fn read_secret(
+    file: Option<&str>,
+    environment_variable: Option<&str>,
+) -> Result<Option<String>>
+{
+    if let Some(file) = file
+    {
+        let secret = std::fs::read_to_string(file).with_context(|| format!("reading secret file {}", file))?;
+        return Ok(Some(secret.trim_end().to_string()));
+    }
+    if let Some(environment_variable) = environment_variable
+    {
+        let secret = std::env::var(environment_variable).with_context(|| format!("reading environment variable {}", environment_variable))?;
+        return Ok(Some(secret));
+    }
+    Ok(None)
+}
+
+pub fn parse_node_exporter_map(
+    raw_scrapes: &HashMap<String, RawScrape>,
+    sample_errors: &mut SampleErrors,
+) -> HashMap<String, Scrape>
+{
+    raw_scrapes.iter().map(|(hostname_port, raw_scrape)| (hostname_port.to_string(), parse_node_exporter(hostname_port, raw_scrape, sample_errors))).collect()
+}
+
+// a scrape that can't be parsed is counted as a sample error of the target, so it is logged once instead of every interval.
 fn parse_node_exporter(
-    node_exporter_data: String
+    hostname_port: &str,
+    raw_scrape: &RawScrape,
+    sample_errors: &mut SampleErrors,
 ) -> Scrape
 {
-    let lines : Vec<_> = node_exporter_data.lines().map(|lines| Ok(lines.to_owned())).collect();
-    Scrape::parse(lines.into_iter()).unwrap()
+    let lines : Vec<_> = raw_scrape.data.lines().map(|lines| Ok(lines.to_owned())).collect();
+    // the timestamp of the fetch is used as the sample time, so a replayed archive produces the same samples.
+    Scrape::parse_at(lines.into_iter(), raw_scrape.timestamp).unwrap_or_else(|error| {
+        debug!("{}: error parsing scrape: {}", hostname_port, error);
+        sample_errors.add(hostname_port, &StatisticError::UnparsableScrape);
+        Scrape { docs: HashMap::new(), samples: Vec::new() }
+    })
 }
 
+/// The interrupts per irq and per cpu are many rows on a large machine, so they are only processed when they are shown (sar-I).
 pub async fn process_statistics(
     node_exporter_values: &HashMap<String, Scrape>,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
+    statistics: &mut Statistics,
+    sample_errors: &mut SampleErrors,
+    interrupts: bool,
 )
 {
     for (hostname, scrape) in node_exporter_values
     {
+        // the samples that could not be processed are skipped for the totals too.
+        let mut processed_samples = Vec::with_capacity(scrape.samples.len());
+        let mut restarted = false;
+        statistics.host_rows_mut(hostname).for_each(|row| row.reset = false);
         for sample in &scrape.samples
         {
-            match sample.metric.as_str()
+            let result = match sample.metric.as_str()
             {
+                // a changed boot time or process start time means the machine or the server restarted,
+                // so every counter of the target starts again from zero.
+                "node_boot_time_seconds" |
+                "process_start_time_seconds" => detect_restart(sample, hostname, statistics).map(|changed| restarted |= changed),
+
                 "node_schedstat_running_seconds_total" |
                 "node_schedstat_waiting_seconds_total" |
                 "node_cpu_seconds_total" |
@@ -719,6 +1381,47 @@ pub async fn process_statistics(
                 "node_softnet_processed_total" |
                 "node_softnet_times_squeezed_total" => node_network::process_statistic(sample, hostname, statistics),
 
+                "node_netstat_TcpExt_ListenOverflows" |
+                "node_netstat_TcpExt_ListenDrops" |
+                "node_netstat_TcpExt_SyncookiesSent" |
+                "node_netstat_TcpExt_SyncookiesRecv" |
+                "node_netstat_TcpExt_SyncookiesFailed" |
+                "node_netstat_TcpExt_TCPAbortOnData" |
+                "node_netstat_TcpExt_TCPAbortOnClose" |
+                "node_netstat_TcpExt_TCPAbortOnMemory" |
+                "node_netstat_TcpExt_TCPAbortOnTimeout" |
+                "node_netstat_TcpExt_TCPAbortOnLinger" |
+                "node_netstat_TcpExt_TCPAbortFailed" |
+                "node_netstat_Tcp_InSegs" |
+                "node_netstat_Tcp_ActiveOpens" |
+                "node_netstat_Tcp_PassiveOpens" |
+                "node_netstat_Tcp_OutSegs" |
+                "node_netstat_Tcp_RetransSegs" |
+                "node_netstat_Tcp_AttemptFails" |
+                "node_netstat_Tcp_EstabResets" |
+                "node_netstat_Tcp_InErrs" |
+                "node_netstat_Tcp_OutRsts" |
+                "node_netstat_Udp_InDatagrams" |
+                "node_netstat_Udp_OutDatagrams" |
+                "node_netstat_Udp_NoPorts" |
+                "node_netstat_Udp_InErrors" |
+                "node_netstat_Ip_InReceives" |
+                "node_netstat_Ip_ForwDatagrams" |
+                "node_netstat_Ip_InDelivers" |
+                "node_netstat_Ip_OutRequests" |
+                "node_netstat_Ip_ReasmReqds" |
+                "node_netstat_Ip_ReasmOKs" |
+                "node_netstat_Ip_FragOKs" |
+                "node_netstat_Ip_FragCreates" |
+                "node_netstat_Ip_InHdrErrors" |
+                "node_netstat_Ip_InAddrErrors" |
+                "node_netstat_Ip_InUnknownProtos" |
+                "node_netstat_Ip_InDiscards" |
+                "node_netstat_Ip_OutDiscards" |
+                "node_netstat_Ip_OutNoRoutes" |
+                "node_netstat_Ip_ReasmFails" |
+                "node_netstat_Ip_FragFails" => node_netstat::process_statistic(sample, hostname, statistics),
+
                 "node_memory_Active_anon_bytes" |
                 "node_memory_Active_bytes" |
                 "node_memory_Active_file_bytes" |
@@ -776,7 +1479,13 @@ pub async fn process_statistics(
                 "node_vmstat_pgpgin" |
                 "node_vmstat_pgpgout" |
                 "node_vmstat_pswpin" |
-                "node_vmstat_pswpout" => node_vmstat::process_statistic(sample, hostname, statistics),
+                "node_vmstat_pswpout" |
+                "node_vmstat_pgscan_kswapd" |
+                "node_vmstat_pgscan_direct" |
+                "node_vmstat_pgsteal_kswapd" |
+                "node_vmstat_pgsteal_direct" => node_vmstat::process_statistic(sample, hostname, statistics),
+
+                "node_interrupts_total" if !interrupts => Ok(()),
 
                 "node_procs_running" |
                 "node_procs_blocked" |
@@ -789,6 +1498,7 @@ pub async fn process_statistics(
                 "node_pressure_memory_stalled_seconds_total" |
                 "node_pressure_memory_waiting_seconds_total" |
                 "node_intr_total" |
+                "node_interrupts_total" |
                 "node_context_switches_total" => node_misc::process_statistic(sample, hostname, statistics),
 
                 "cpu_stime" |
@@ -860,33 +1570,241 @@ pub async fn process_statistics(
                 "rocksdb_sst_read_micros_count" |
                 "rocksdb_sst_read_micros_sum" => yb_io::process_statistic(sample, hostname, statistics),
 
-                &_ => {},
+                &_ => Ok(()),
+            };
+            match result
+            {
+                Ok(()) => processed_samples.push(sample),
+                Err(error) => sample_errors.add(hostname, &error),
             }
         }
-        // this is highly inefficient currently, because it will visit every single statistic.
-        // So statistics like single vcpu statistics, per disk statistics and per NIC statistics will all likely be re-revisited.
-        for sample in &scrape.samples
+        // the totals are calculated once per metric, after all the samples (cpus, devices) of the metric are processed.
+        for metric in processed_samples.iter().map(|sample| &sample.metric).unique()
         {
-            node_cpu::create_total(sample, hostname, statistics);
-            node_disk::create_total(sample, hostname, statistics);
-            node_network::create_total(sample, hostname, statistics);
+            node_cpu::create_total(metric, hostname, statistics);
+            node_disk::create_total(metric, hostname, statistics);
+            node_network::create_total(metric, hostname, statistics);
+            node_misc::create_total(metric, hostname, statistics);
         };
+        // a counter that went down is a new baseline by itself (update_counter), together with its total.
+        // after a restart all counters of the target started again, so all rows of the target are taken as a new baseline,
+        // which keeps them out of the output and the historical data for this interval.
+        if statistics.host_rows(hostname).any(|row| row.reset) && !restarted
+        {
+            info!("{}: counter reset detected, starting a new baseline for the counter", hostname);
+        }
+        if restarted
+        {
+            info!("{}: restart detected, starting a new baseline", hostname);
+            statistics.host_rows_mut(hostname).for_each(|row| { row.first_value = true; row.reset = true; });
+        }
     }
 }
 
+fn detect_restart(
+    sample: &Sample,
+    hostname: &str,
+    statistics: &mut Statistics,
+) -> Result<bool, StatisticError>
+{
+    let value = match sample.value
+    {
+        Value::Gauge(value) | Value::Untyped(value) => value,
+        _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Gauge or Untyped" }),
+    };
+    let mut changed = false;
+    statistics
+        .entry(hostname, &sample.metric, "", "")
+        .and_modify(|row| {
+            changed = row.last_value != value;
+            row.last_value = value;
+            row.last_timestamp = sample.timestamp;
+            row.first_value = false;
+        })
+        .or_insert(
+            Statistic {
+                last_value: value,
+                last_timestamp: sample.timestamp,
+                first_value: true,
+                ..Default::default()
+            }
+        );
+    Ok(changed)
+}
 
+pub fn print_counter_resets(
+    statistics: &Statistics,
+)
+{
+    for (hostname, row) in statistics.hosts().filter_map(|hostname| statistics.host_rows(hostname).find(|row| row.reset).map(|row| (hostname, row)))
+    {
+        println!("{:30} {:8} ** counter reset or restart detected, values are a new baseline **", hostname, format_time(row.last_timestamp));
+    }
+}
 
+/// Write a counter-reset row for every host with a counter reset or restart, the machine readable version of print_counter_resets.
+pub fn write_counter_resets(
+    statistics: &Statistics,
+    row_writer: &mut RowWriter,
+) -> Result<()>
+{
+    for (hostname, row) in statistics.hosts().filter_map(|hostname| statistics.host_rows(hostname).find(|row| row.reset).map(|row| (hostname, row)))
+    {
+        row_writer.write_marker("counter-reset", hostname, row.last_timestamp, &[("status", serde_json::Value::from("new baseline"))])?;
+    }
+    Ok(())
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn update_counter_per_second() {
+        let start = Utc::now();
+        let mut statistic = Statistic { last_value: 100., last_timestamp: start, first_value: true, ..Default::default() };
+        statistic.update_counter(300., start + chrono::Duration::seconds(2));
+        assert_eq!(statistic.delta_value, 200.);
+        assert_eq!(statistic.per_second_value, 100.);
+        assert!(!statistic.first_value);
+        assert!(!statistic.reset);
+    }
 
+    #[test]
+    fn update_counter_reset() {
+        let start = Utc::now();
+        let mut statistic = Statistic { last_value: 1000., last_timestamp: start, ..Default::default() };
+        statistic.update_counter(10., start + chrono::Duration::seconds(1));
+        // the lower value is a new baseline: no difference, and the row is flagged like a first value.
+        assert_eq!(statistic.delta_value, 0.);
+        assert_eq!(statistic.per_second_value, 0.);
+        assert_eq!(statistic.last_value, 10.);
+        assert!(statistic.reset);
+        assert!(statistic.first_value);
+        // the interval after the reset is calculated from the new baseline.
+        statistic.update_counter(30., start + chrono::Duration::seconds(2));
+        assert_eq!(statistic.per_second_value, 20.);
+        assert!(!statistic.reset);
+        assert!(!statistic.first_value);
+    }
 
+    #[test]
+    fn history_skips_a_reset_device() {
+        let timestamp = Utc::now();
+        let mut statistics = Statistics::default();
+        statistics.entry("node1:9100:metrics", "node_disk_read_bytes_total", "sda", "").or_insert(Statistic { last_timestamp: timestamp, per_second_value: 100., ..Default::default() });
+        statistics.entry("node1:9100:metrics", "node_disk_read_bytes_total", "sdb", "").or_insert(Statistic { last_timestamp: timestamp, per_second_value: 200., ..Default::default() });
+        // only the written bytes of sdb were reset.
+        statistics.entry("node1:9100:metrics", "node_disk_written_bytes_total", "sdb", "").or_insert(Statistic { last_timestamp: timestamp, first_value: true, reset: true, ..Default::default() });
+        let mut historical_data = HistoricalData::new(PerCpuHistory::None);
+        historical_data.add_node_disk_statistics(&statistics);
+        assert_eq!(historical_data.disk_details.keys().map(|(_, _, device)| device.as_str()).collect::<Vec<_>>(), vec!["sda"]);
+    }
 
+    #[test]
+    fn create_total_per_second_label() {
+        let mut statistics = Statistics::default();
+        for (cpu, mode, per_second_value) in [("0", "user", 0.25), ("1", "user", 0.5), ("0", "system", 0.125), ("1", "system", 0.)]
+        {
+            statistics.entry("node1:9100:metrics", "node_cpu_seconds_total", cpu, mode).or_insert(Statistic { per_second_value, ..Default::default() });
+        }
+        // the total is made again every interval, without adding the total of the previous interval.
+        for _ in 0..2
+        {
+            statistics.create_total("node1:9100:metrics", "node_cpu_seconds_total");
+            assert_eq!(statistics.get("node1:9100:metrics", "node_cpu_seconds_total", "total", "user").map(|total| total.per_second_value), Some(0.75));
+            assert_eq!(statistics.get("node1:9100:metrics", "node_cpu_seconds_total", "total", "system").map(|total| total.per_second_value), Some(0.125));
+        }
+        assert_eq!(statistics.rows("node1:9100:metrics", "node_cpu_seconds_total").count(), 6);
+    }
 
+    #[test]
+    fn create_total_with_a_reset_row() {
+        let mut statistics = Statistics::default();
+        statistics.entry("node1:9100:metrics", "node_disk_read_bytes_total", "sda", "").or_insert(Statistic { per_second_value: 100., ..Default::default() });
+        statistics.entry("node1:9100:metrics", "node_disk_read_bytes_total", "sdb", "").or_insert(Statistic { first_value: true, reset: true, ..Default::default() });
+        statistics.create_total("node1:9100:metrics", "node_disk_read_bytes_total");
+        let total = statistics.get("node1:9100:metrics", "node_disk_read_bytes_total", "total", "").unwrap();
+        assert!(total.reset);
+        assert!(total.first_value);
+    }
 
+    #[tokio::test]
+    async fn process_statistics_totals() {
+        let start = Utc::now();
+        let scrape = |timestamp: DateTime<Utc>, seconds: f64| HashMap::from([("node1:9100:metrics".to_string(), RawScrape {
+            timestamp,
+            data: format!("# TYPE node_cpu_seconds_total counter\nnode_cpu_seconds_total{{cpu=\"0\",mode=\"user\"}} {}\nnode_cpu_seconds_total{{cpu=\"1\",mode=\"user\"}} {}\n", seconds, seconds * 2.),
+        })]);
+        let mut statistics = Statistics::default();
+        let mut sample_errors = SampleErrors::default();
+        for (interval, seconds) in [(0, 10.), (1, 10.5)]
+        {
+            let raw_scrapes = scrape(start + chrono::Duration::seconds(interval), seconds);
+            process_statistics(&parse_node_exporter_map(&raw_scrapes, &mut sample_errors), &mut statistics, &mut sample_errors, false).await;
+        }
+        let total = statistics.get("node1:9100:metrics", "node_cpu_seconds_total", "total", "user").unwrap();
+        assert!(!total.first_value);
+        assert_eq!(total.per_second_value, 1.5);
+    }
 
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("0.5").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
 
+    #[test]
+    fn parse_duration_invalid() {
+        for duration in ["", "0", "-1", "1d", "ms", "inf", "NaN"]
+        {
+            assert!(parse_duration(duration).is_err(), "duration: {}", duration);
+        }
+    }
 
+    #[test]
+    fn averages_per_host_and_label() {
+        let mut averages = Averages::default();
+        assert!(averages.is_empty());
+        averages.add("node1:9100:metrics", "sda", &[], &[1., 10.]);
+        averages.add("node1:9100:metrics", "sda", &[], &[3., 30.]);
+        averages.add("node1:9100:metrics", "sda", &[], &[2., 5.]);
+        averages.add("node1:9100:metrics", "sdb", &[], &[4., 40.]);
+        let no_labels = Labels::new();
+        assert_eq!(averages.summary(false), vec![
+            ("average", "node1:9100:metrics", "sda", &no_labels, vec![2., 15.]),
+            ("average", "node1:9100:metrics", "sdb", &no_labels, vec![4., 40.]),
+        ]);
+        assert_eq!(averages.summary(true), vec![
+            ("average", "node1:9100:metrics", "sda", &no_labels, vec![2., 15.]),
+            ("min", "node1:9100:metrics", "sda", &no_labels, vec![1., 5.]),
+            ("max", "node1:9100:metrics", "sda", &no_labels, vec![3., 30.]),
+            ("average", "node1:9100:metrics", "sdb", &no_labels, vec![4., 40.]),
+            ("min", "node1:9100:metrics", "sdb", &no_labels, vec![4., 40.]),
+            ("max", "node1:9100:metrics", "sdb", &no_labels, vec![4., 40.]),
+        ]);
+    }
 
+    #[test]
+    fn averages_per_extra_labels() {
+        let mut averages = Averages::default();
+        let cpu = |cpu: &str| vec![("cpu".to_string(), cpu.to_string())];
+        averages.add("node1:9100:metrics", "LOC", &cpu("0"), &[100.]);
+        averages.add("node1:9100:metrics", "LOC", &cpu("1"), &[300.]);
+        averages.add("node1:9100:metrics", "LOC", &cpu("0"), &[200.]);
+        let summary = averages.summary(false);
+        assert_eq!(summary.iter().map(|(_, _, _, labels, values)| (labels[0].1.as_str(), values[0])).collect::<Vec<_>>(), vec![("0", 150.), ("1", 300.)]);
+    }
 
-
-
+    #[test]
+    fn averages_restart_when_the_fields_change() {
+        let mut averages = Averages::default();
+        averages.add("node1:9100:metrics", "", &[], &[1., 2.]);
+        averages.add("node1:9100:metrics", "", &[], &[5.]);
+        assert_eq!(averages.summary(true).into_iter().map(|(kind, _, _, _, values)| (kind, values)).collect::<Vec<_>>(), vec![("average", vec![5.]), ("min", vec![5.]), ("max", vec![5.])]);
+    }
+}
@@ -1,28 +1,37 @@
-use clap::{Parser, ValueEnum};
-use tokio::time;
+use clap::{builder::RangedU64ValueParser, ArgGroup, Parser, ValueEnum};
+use tokio::time::{self, MissedTickBehavior};
 use time::Duration;
-//use anyhow::Result;
-//use log::*;
-use std::collections::BTreeMap;
+use anyhow::Context;
+use log::*;
+use itertools::Itertools;
 //use ctrlc;
-use std::{process, sync::{Arc, Mutex}};
+use std::{collections::BTreeMap, fs, io::{self, Write}, process, sync::{Arc, Mutex}};
 //use std::sync::atomic::{AtomicBool, Ordering};
 //use std::thread::sleep;
 
 
-use dsar::{read_node_exporter_into_map, process_statistics, Statistic, HistoricalData};
-use dsar::node_cpu::{print_sar_u, print_sar_u_header, create_cpu_plots};
-use dsar::node_disk::{print_sar_d, print_sar_d_header, print_iostat, print_iostat_header, print_iostat_x, print_iostat_x_header, print_xfs_iops, print_xfs_iops_header, create_disk_plots};
-use dsar::node_network::{print_sar_n_dev, print_sar_n_dev_header, print_sar_n_edev, print_sar_n_edev_header, print_sar_n_sock, print_sar_n_sock_header, print_sar_n_sock6, print_sar_n_sock6_header, print_sar_n_soft, print_sar_n_soft_header, create_network_plots};
-use dsar::node_memory::{create_memory_plots, print_sar_r, print_sar_r_header, print_sar_s, print_sar_s_header};
-use dsar::node_vmstat::{print_sar_b, print_sar_b_header, print_sar_w, print_sar_w_header, print_vmstat, print_vmstat_header, create_vmstat_plots};
-use dsar::node_misc::{print_sar_q, print_sar_q_header, print_psi, print_psi_header, create_misc_plots};
-use dsar::yb_cpu::{print_yb_cpu, print_yb_cpu_header};
-use dsar::yb_network::{print_yb_network, print_yb_network_header};
-use dsar::yb_memory::{print_yb_memory, print_yb_memory_header, create_yb_memory_plots};
-use dsar::yb_io::{print_yb_io, print_yb_io_header, create_yb_io_plots};
+use chrono::{DateTime, Utc};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+use dsar::{format_time, parse_duration, parse_node_exporter_map, process_statistics, read_secret, set_subsecond_time, subsecond_time, print_counter_resets, write_counter_resets, Averages, Labels, Row, Statistics, HistoricalData, PerCpuHistory, ScrapeOptions, SampleErrors};
+use dsar::target::{Target, TargetFile, targets_from_hosts_ports};
+use dsar::discovery::YbDiscovery;
+use dsar::scraper::{Scraper, TargetHealth, print_failed_targets, write_failed_targets, print_target_health_summary};
+use dsar::output::{OutputFormat, RowWriter, View};
+use dsar::exporter::Exporter;
+use dsar::archive::{ArchiveWriter, ArchiveReader, ArchiveInterval, TimeBound};
+use dsar::node_cpu::{CpuList, sar_u_rows, print_sar_u_row, print_sar_u_header, SAR_U_FIELDS, SAR_U_ALL_FIELDS, CPU_ALL_FIELDS, create_cpu_plots, create_cpu_heatmap_plots};
+use dsar::node_disk::{sar_d_rows, print_sar_d_row, print_sar_d_header, SAR_D_FIELDS, iostat_rows, print_iostat_row, print_iostat_header, IOSTAT_FIELDS, iostat_x_rows, print_iostat_x_row, print_iostat_x_header, IOSTAT_X_FIELDS, xfs_iops_rows, print_xfs_iops_row, print_xfs_iops_header, XFS_IOPS_FIELDS, create_disk_plots};
+use dsar::node_network::{sar_n_dev_rows, print_sar_n_dev_row, print_sar_n_dev_header, SAR_N_DEV_FIELDS, sar_n_edev_rows, print_sar_n_edev_row, print_sar_n_edev_header, SAR_N_EDEV_FIELDS, sar_n_sock_rows, print_sar_n_sock_row, print_sar_n_sock_header, SAR_N_SOCK_FIELDS, sar_n_sock6_rows, print_sar_n_sock6_row, print_sar_n_sock6_header, SAR_N_SOCK6_FIELDS, sar_n_soft_rows, print_sar_n_soft_row, print_sar_n_soft_header, SAR_N_SOFT_FIELDS, create_network_plots};
+use dsar::node_netstat::{sar_n_tcp_rows, print_sar_n_tcp_row, print_sar_n_tcp_header, SAR_N_TCP_FIELDS, sar_n_etcp_rows, print_sar_n_etcp_row, print_sar_n_etcp_header, SAR_N_ETCP_FIELDS, sar_n_udp_rows, print_sar_n_udp_row, print_sar_n_udp_header, SAR_N_UDP_FIELDS, sar_n_ip_rows, print_sar_n_ip_row, print_sar_n_ip_header, SAR_N_IP_FIELDS, sar_n_eip_rows, print_sar_n_eip_row, print_sar_n_eip_header, SAR_N_EIP_FIELDS, tcp_listen_rows, print_tcp_listen_row, print_tcp_listen_header, TCP_LISTEN_FIELDS, create_netstat_plots};
+use dsar::node_memory::{create_memory_plots, sar_r_rows, print_sar_r_row, print_sar_r_header, SAR_R_FIELDS, SAR_R_ALL_FIELDS, MEM_RELEVANT_FIELDS, sar_s_rows, print_sar_s_row, print_sar_s_header, SAR_S_FIELDS};
+use dsar::node_vmstat::{sar_b_rows, print_sar_b_row, print_sar_b_header, SAR_B_FIELDS, sar_w_rows, print_sar_w_row, print_sar_w_header, SAR_W_FIELDS, vmstat_rows, print_vmstat_row, print_vmstat_header, VMSTAT_FIELDS, create_vmstat_plots};
+use dsar::node_misc::{sar_q_rows, print_sar_q_row, print_sar_q_header, SAR_Q_FIELDS, psi_rows, print_psi_row, print_psi_header, PSI_FIELDS, sar_i_rows, print_sar_i_row, print_sar_i_header, SAR_I_FIELDS, create_misc_plots};
+use dsar::yb_cpu::{yb_cpu_rows, print_yb_cpu_row, print_yb_cpu_header, YB_CPU_FIELDS};
+use dsar::yb_network::{yb_network_rows, print_yb_network_row, print_yb_network_header, YB_NETWORK_FIELDS};
+use dsar::yb_memory::{yb_memory_rows, print_yb_memory_row, print_yb_memory_header, YB_MEMORY_FIELDS, create_yb_memory_plots};
+use dsar::yb_io::{yb_io_rows, print_yb_io_row, print_yb_io_header, YB_IO_FIELDS, create_yb_io_plots};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
 enum OutputOptions
 {
     SarU,
@@ -34,6 +43,8 @@ enum OutputOptions
     #[clap(name = "sar-W")]
     SarW,
     SarQ,
+    #[clap(name = "sar-I")]
+    SarI,
     #[clap(name = "sar-B")]
     SarB,
     #[clap(name = "sar-n-DEV")]
@@ -46,6 +57,17 @@ enum OutputOptions
     SarNSock6,
     #[clap(name = "sar-n-SOFT")]
     SarNSoft,
+    #[clap(name = "sar-n-TCP")]
+    SarNTcp,
+    #[clap(name = "sar-n-ETCP")]
+    SarNEtcp,
+    #[clap(name = "sar-n-UDP")]
+    SarNUdp,
+    #[clap(name = "sar-n-IP")]
+    SarNIp,
+    #[clap(name = "sar-n-EIP")]
+    SarNEip,
+    TcpListen,
     SarR,
     #[clap(name = "sar-r-ALL")]
     SarRAll,
@@ -64,6 +86,7 @@ enum OutputOptions
 
 #[derive(Debug, Parser)]
 #[clap(version, about, long_about = None)]
+#[clap(group(ArgGroup::new("history").args(["replay", "plot"])))]
 pub struct Opts
 {
     /// Hostnames
@@ -72,21 +95,132 @@ pub struct Opts
     /// Ports
     #[arg(short = 'P', long, value_name = "port,port", default_value = "9100")]
     ports: String,
-    /// Interval
-    #[arg(short = 'i', long, value_name = "time (s)", default_value = "1")]
-    interval: u64,
-    /// Parallel
-    #[arg(short = 'p', long, value_name = "parallel", default_value = "3")]
+    /// Targets, instead of every combination of hostnames, ports and endpoints
+    #[arg(short = 'T', long, value_name = "[scheme://]host:port[/path],..", conflicts_with_all = ["hosts", "ports"])]
+    targets: Option<String>,
+    /// File with the targets, one per line, read again when changed
+    #[arg(long, value_name = "file", conflicts_with_all = ["hosts", "ports", "targets"])]
+    target_file: Option<String>,
+    /// Prometheus file_sd file (json or yaml) with the targets, read again when changed
+    #[arg(long, value_name = "file", conflicts_with_all = ["hosts", "ports", "targets", "target_file"])]
+    file_sd: Option<String>,
+    /// YugabyteDB masters to discover the tablet servers and masters to scrape from
+    #[arg(long, value_name = "host:port,host:port", conflicts_with_all = ["hosts", "ports", "targets", "target_file", "file_sd"])]
+    yb_masters: Option<String>,
+    /// node_exporter port on the discovered YugabyteDB hosts
+    #[arg(long, value_name = "port", default_value = "9100")]
+    yb_node_exporter_port: u16,
+    /// Interval to query the YugabyteDB masters again
+    #[arg(long, value_name = "time (s)", default_value = "60")]
+    yb_discovery_interval: u64,
+    /// Scheme, for the hosts and targets that are not prefixed with one (http://hostname or https://hostname)
+    #[arg(long, value_name = "scheme", default_value = "http", value_parser = ["http", "https"])]
+    scheme: String,
+    /// CA certificate (PEM) to verify the https endpoints with, in addition to the system CA certificates
+    #[arg(long, value_name = "file")]
+    cacert: Option<String>,
+    /// Don't verify the certificates of the https endpoints
+    #[arg(long, conflicts_with = "cacert")]
+    insecure_skip_verify: bool,
+    /// Client certificate (PEM) for mutual TLS
+    #[arg(long, value_name = "file", requires = "key")]
+    cert: Option<String>,
+    /// Client private key (PEM, PKCS#8) for mutual TLS
+    #[arg(long, value_name = "file", requires = "cert")]
+    key: Option<String>,
+    /// Username for basic authentication
+    #[arg(long, value_name = "username")]
+    username: Option<String>,
+    /// File with the password for basic authentication
+    #[arg(long, value_name = "file", requires = "username", conflicts_with = "password_env")]
+    password_file: Option<String>,
+    /// Environment variable with the password for basic authentication
+    #[arg(long, value_name = "variable", requires = "username")]
+    password_env: Option<String>,
+    /// File with the bearer token
+    #[arg(long, value_name = "file", conflicts_with_all = ["bearer_token_env", "username"])]
+    bearer_token_file: Option<String>,
+    /// Environment variable with the bearer token
+    #[arg(long, value_name = "variable", conflicts_with = "username")]
+    bearer_token_env: Option<String>,
+    /// Interval, in seconds (0.5) or milliseconds (500ms)
+    #[arg(short = 'i', long, value_name = "time (s|ms)", default_value = "1", value_parser = parse_duration)]
+    interval: Duration,
+    /// Number of intervals to show, after which dsar stops
+    #[arg(short = 'c', long, value_name = "count", conflicts_with_all = ["replay", "plot"])]
+    count: Option<u64>,
+    /// Time to run, after which dsar stops, in seconds or with a unit (500ms, 2s, 10m, 1h)
+    #[arg(long, value_name = "time", value_parser = parse_duration, conflicts_with_all = ["replay", "plot"])]
+    duration: Option<Duration>,
+    /// Time (UTC) at which dsar stops
+    #[arg(long, value_name = "[YYYY-MM-DD ]HH:MM:SS", value_parser = TimeBound::parse, conflicts_with_all = ["replay", "plot"])]
+    until: Option<TimeBound>,
+    /// Parallel scrapes
+    #[arg(short = 'p', long, value_name = "parallel", default_value = "3", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
     parallel: usize,
+    /// Parallel scrapes per host
+    #[arg(long, value_name = "parallel", default_value = "2", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    parallel_per_host: usize,
+    /// Maximum time between the scrapes of a failing target, 0 disables backoff
+    #[arg(long, value_name = "time (s)", default_value = "60")]
+    backoff_max: u64,
     /// Print header
     #[arg(short = 'n', long, value_name = "nr", default_value = "5")]
     header_print: u64,
-    /// Output
-    #[arg(short = 'o', long, value_name = "option", value_enum, default_value_t = OutputOptions::SarU )]
-    output: OutputOptions,
+    /// Output, one or more views that share the scrapes
+    #[arg(short = 'o', long, value_name = "option,option", value_enum, value_delimiter = ',', default_value = "sar-u")]
+    output: Vec<OutputOptions>,
+    /// Output format: text columns, or every row as a JSON object (json, ndjson)
+    #[arg(long, value_name = "format", value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// File to write the json or ndjson rows to, instead of stdout
+    #[arg(long, value_name = "file")]
+    output_file: Option<String>,
+    /// Print Min: and Max: rows with the Average: rows at the end of the run
+    #[arg(long)]
+    minmax: bool,
+    /// Show the cpus separately in sar-u, sar-u-ALL, cpu-all and sar-I, like sar -P: ALL for the total and every cpu, or a list of cpus, such as 0,2-5
+    #[arg(long, value_name = "ALL|cpu,cpu-cpu", value_parser = CpuList::parse)]
+    cpus: Option<CpuList>,
+    /// Only show the n interrupts with the highest rate of every host in sar-I
+    #[arg(long, value_name = "n")]
+    irq_top: Option<usize>,
+    /// Serve the values of the output views of the last interval as prometheus metrics on http://address/metrics
+    #[arg(long, value_name = "address:port", conflicts_with_all = ["replay", "plot"])]
+    listen: Option<String>,
     /// Graph
     #[arg(short = 'g', long, value_name = "graph")]
     graph: bool,
+    /// Record the scrapes to an archive file
+    #[arg(long, value_name = "file")]
+    record: Option<String>,
+    /// Rotate the archive file when it would exceed the size
+    #[arg(long, value_name = "size (MB)")]
+    record_max_size: Option<u64>,
+    /// Rotate the archive file when the day changes
+    #[arg(long)]
+    record_daily: bool,
+    /// Replay the scrapes from archive files, in the order given
+    #[arg(long, value_name = "file,file", conflicts_with = "record")]
+    replay: Option<String>,
+    /// Start time (UTC) for replay and plot
+    #[arg(long, value_name = "[YYYY-MM-DD ]HH:MM:SS", value_parser = TimeBound::parse, requires = "history")]
+    start: Option<TimeBound>,
+    /// End time (UTC) for replay and plot
+    #[arg(long, value_name = "[YYYY-MM-DD ]HH:MM:SS", value_parser = TimeBound::parse, requires = "history")]
+    end: Option<TimeBound>,
+    /// Save the historical data to a file at exit
+    #[arg(long, value_name = "file")]
+    save_history: Option<String>,
+    /// Export the historical data to csv files at exit, or with plot, one file per category: prefix_cpu.csv, prefix_disk.csv, ..
+    #[arg(long, value_name = "prefix")]
+    csv: Option<String>,
+    /// Create the plots from a saved historical data file
+    #[arg(long, value_name = "file", conflicts_with_all = ["replay", "record", "save_history"])]
+    plot: Option<String>,
+    /// Hosts to plot
+    #[arg(long, value_name = "hostname,hostname", requires = "plot")]
+    host_filter: Option<String>,
 }
 
 #[tokio::main]
@@ -95,94 +229,870 @@ async fn main()
     env_logger::init();
     let args = Opts::parse();
 
-    let mut interval = time::interval(Duration::from_secs(args.interval));
-    let mut statistics: BTreeMap<(String, String, String, String), Statistic> = Default::default();
-    let historical_data : Arc<Mutex<HistoricalData>> = Arc::new(Mutex::new(HistoricalData::new()));
-    let historical_data_ctrlc = historical_data.clone();
-    let historical_data_loop = historical_data.clone();
-
-    ctrlc::set_handler(move || {
-        if args.graph
-        {
-            create_cpu_plots(&historical_data_ctrlc);
-            create_disk_plots(&historical_data_ctrlc);
-            create_memory_plots(&historical_data_ctrlc);
-            create_yb_memory_plots(&historical_data_ctrlc);
-            create_yb_io_plots(&historical_data_ctrlc);
-            create_misc_plots(&historical_data_ctrlc);
-            create_network_plots(&historical_data_ctrlc);
-            create_vmstat_plots(&historical_data_ctrlc);
-        };
+    if let Some(file) = &args.plot
+    {
+        plot(file, &args);
         process::exit(0);
-    }).unwrap();
+    }
+    if args.replay.is_some()
+    {
+        replay(&args).await;
+    }
+
+    // the time column shows milliseconds if the interval isn't a whole number of seconds.
+    set_subsecond_time(args.interval.subsec_nanos() != 0);
+    let mut interval = time::interval(args.interval);
+    // when an interval takes too long, the missed ticks are skipped instead of run in a burst.
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut statistics: Statistics = Default::default();
+    let historical_data : Arc<Mutex<HistoricalData>> = Arc::new(Mutex::new(HistoricalData::new(per_cpu_history(&args))));
+    let scrape_options = create_scrape_options(&args).unwrap_or_else(|error| {
+        error!("{:#}", error);
+        process::exit(1);
+    });
+    let scraper = Scraper::new(&scrape_options, args.parallel, args.parallel_per_host, args.interval, Duration::from_secs(args.backoff_max)).unwrap_or_else(|error| {
+        error!("{:#}", error);
+        process::exit(1);
+    });
+    let mut target_file = args.target_file.as_ref().map(|file| (file, false))
+        .or(args.file_sd.as_ref().map(|file| (file, true)))
+        .map(|(file, file_sd)| TargetFile::new(file, file_sd, &args.scheme).unwrap_or_else(|error| {
+            error!("{:#}", error);
+            process::exit(1);
+        }));
+    let mut yb_discovery = match &args.yb_masters
+    {
+        Some(masters) => Some(YbDiscovery::new(&masters.split(',').collect::<Vec<_>>(), args.yb_node_exporter_port, Duration::from_secs(args.yb_discovery_interval), args.interval, &args.scheme, &scrape_options).await.unwrap_or_else(|error| {
+            error!("{:#}", error);
+            process::exit(1);
+        })),
+        None => None,
+    };
+    let mut targets = if let Some(target_file) = &target_file
+    {
+        target_file.targets.clone()
+    }
+    else if let Some(yb_discovery) = &yb_discovery
+    {
+        yb_discovery.targets.clone()
+    }
+    else
+    {
+        create_targets(&args).unwrap_or_else(|error| {
+            error!("{:#}", error);
+            process::exit(1);
+        })
+    };
+    let mut archive = args.record.as_ref().map(|file| ArchiveWriter::new(file, args.record_max_size, args.record_daily).unwrap_or_else(|error| {
+        error!("{:#}", error);
+        process::exit(1);
+    }));
+
+    // with the hostname and port combinations most endpoints don't exist, so only the targets that were scraped successfully before are reported.
+    let report_never_succeeded = args.targets.is_some() || args.target_file.is_some() || args.file_sd.is_some() || args.yb_masters.is_some();
+    let target_health = scraper.health();
+    let mut sample_errors = SampleErrors::default();
+    let outputs = unique_outputs(&args.output);
+    let mut view_options = ViewOptions { cpus: args.cpus.clone(), irq_top: args.irq_top, target_labels: create_target_labels(&targets) };
+    let row_writer = create_row_writer(&args);
+    let exporter = match &args.listen
+    {
+        Some(listen) => Some(Exporter::start(listen).await.unwrap_or_else(|error| {
+            error!("{:#}", error);
+            process::exit(1);
+        })),
+        None => None,
+    };
+    let exit_state = ExitState {
+        historical_data: historical_data.clone(),
+        target_health: scraper.health(),
+        // a copy for the summary at exit, because the lock can't be held while processing the statistics.
+        sample_errors: Arc::new(Mutex::new(SampleErrors::default())),
+        report_never_succeeded,
+        graph: args.graph,
+        save_history: args.save_history.clone(),
+        csv: args.csv.clone(),
+        outputs: outputs.clone(),
+        view_options: view_options.clone(),
+        averages: Arc::new(Mutex::new(vec![Averages::default(); outputs.len()])),
+        minmax: args.minmax,
+        row_writer: row_writer.clone(),
+        text_summaries: row_writer.is_none() || args.output_file.is_some(),
+    };
+    let exit_state_ctrlc = exit_state.clone();
+    ctrlc::set_handler(move || finish(&exit_state_ctrlc, true)).unwrap();
+
+    // the run ends after the duration, or at the until time, whichever comes first.
+    let start = time::Instant::now();
+    let until = args.until.map(|until| {
+        let until = until.next_after(Utc::now());
+        (until - Utc::now()).to_std().map(|wait| start + wait).unwrap_or_else(|_| {
+            error!("--until {} is in the past", until);
+            process::exit(1);
+        })
+    });
+    let end = [args.duration.map(|duration| start + duration), until].into_iter().flatten().min();
 
     let mut print_counter: u64 = 0;
+    let mut overruns: u64 = 0;
+    let mut last_overrun_warning: Option<time::Instant> = None;
     loop
     {
-        interval.tick().await;
-
-        let node_exporter_values = read_node_exporter_into_map(&args.hosts.split(',').collect(), &args.ports.split(',').collect(), args.parallel).await;
-        process_statistics(&node_exporter_values, &mut statistics).await;
-        historical_data_loop.lock().unwrap().add(&statistics);
-
-        if print_counter == 0 || print_counter % args.header_print == 0
-        {
-            match args.output {
-                OutputOptions::SarU => print_sar_u_header("normal"),
-                OutputOptions::SarUAll => print_sar_u_header("all"),
-                OutputOptions::SarD => print_sar_d_header(),
-                OutputOptions::SarS => print_sar_s_header(),
-                OutputOptions::SarW => print_sar_w_header(),
-                OutputOptions::SarB => print_sar_b_header(),
-                OutputOptions::SarQ => print_sar_q_header(),
-                OutputOptions::SarNDev => print_sar_n_dev_header(),
-                OutputOptions::SarNEdev => print_sar_n_edev_header(),
-                OutputOptions::SarNSock => print_sar_n_sock_header(),
-                OutputOptions::SarNSock6 => print_sar_n_sock6_header(),
-                OutputOptions::SarNSoft => print_sar_n_soft_header(),
-                OutputOptions::SarR => print_sar_r_header("normal"),
-                OutputOptions::SarRAll => print_sar_r_header("all"),
-                OutputOptions::Iostat => print_iostat_header(),
-                OutputOptions::IostatX => print_iostat_x_header(),
-                OutputOptions::YbCpu => print_yb_cpu_header(),
-                OutputOptions::YbNetwork => print_yb_network_header(),
-                OutputOptions::YbMemory => print_yb_memory_header(),
-                OutputOptions::YbIo => print_yb_io_header(),
-                OutputOptions::CpuAll => print_sar_u_header("extended"),
-                OutputOptions::XfsIops => print_xfs_iops_header(),
-                OutputOptions::MemRelevant => print_sar_r_header("relevant"),
-                OutputOptions::Vmstat => print_vmstat_header(),
-                OutputOptions::Psi => print_psi_header(),
+        let interval_start = interval.tick().await;
+        let interval_timestamp = Utc::now();
+
+        let mut changed_targets = None;
+        if let Some(target_file) = target_file.as_mut()
+        {
+            match target_file.reload_if_changed()
+            {
+                Ok(true) => changed_targets = Some(target_file.targets.clone()),
+                Ok(false) => {},
+                Err(error) => warn!("{:#}, keeping the current targets", error),
             }
-        };
-        match args.output {
-            OutputOptions::SarU => print_sar_u("normal", &statistics),
-            OutputOptions::SarUAll => print_sar_u("all", &statistics),
-            OutputOptions::SarD => print_sar_d(&statistics),
-            OutputOptions::SarS => print_sar_s(&statistics),
-            OutputOptions::SarW => print_sar_w(&statistics),
-            OutputOptions::SarB => print_sar_b(&statistics),
-            OutputOptions::SarQ => print_sar_q(&statistics),
-            OutputOptions::SarNDev => print_sar_n_dev(&statistics),
-            OutputOptions::SarNEdev => print_sar_n_edev(&statistics),
-            OutputOptions::SarNSock => print_sar_n_sock(&statistics),
-            OutputOptions::SarNSock6 => print_sar_n_sock6(&statistics),
-            OutputOptions::SarNSoft => print_sar_n_soft(&statistics),
-            OutputOptions::SarR => print_sar_r("normal", &statistics),
-            OutputOptions::SarRAll => print_sar_r("all", &statistics),
-            OutputOptions::Iostat => print_iostat(&statistics),
-            OutputOptions::IostatX => print_iostat_x(&statistics),
-            OutputOptions::YbCpu => print_yb_cpu(&statistics),
-            OutputOptions::YbNetwork => print_yb_network(&statistics),
-            OutputOptions::YbMemory => print_yb_memory(&statistics),
-            OutputOptions::YbIo => print_yb_io(&statistics),
-            OutputOptions::CpuAll => print_sar_u("extended", &statistics),
-            OutputOptions::XfsIops => print_xfs_iops(&statistics),
-            OutputOptions::MemRelevant => print_sar_r("relevant", &statistics),
-            OutputOptions::Vmstat => print_vmstat(&statistics),
-            OutputOptions::Psi => print_psi(&statistics),
+        }
+        if let Some(yb_discovery) = yb_discovery.as_mut()
+        {
+            match yb_discovery.refresh_if_due().await
+            {
+                Ok(true) => changed_targets = Some(yb_discovery.targets.clone()),
+                Ok(false) => {},
+                Err(error) => warn!("{:#}, keeping the current targets", error),
+            }
+        }
+        if let Some(changed_targets) = changed_targets
+        {
+            targets = changed_targets;
+            // the statistics of targets that were removed would otherwise be printed forever.
+            let target_names: Vec<String> = targets.iter().map(|target| target.name()).collect();
+            statistics.retain_hosts(|hostname| target_names.contains(hostname));
+            view_options.target_labels = create_target_labels(&targets);
+        }
+
+        let raw_node_exporter_values = scraper.scrape(&targets).await;
+        if let Some(archive) = archive.as_mut()
+        {
+            archive.write(interval_timestamp, &raw_node_exporter_values).unwrap_or_else(|error| error!("{:#}", error));
+        }
+        // the statistics of a failed target are removed, so its last values are not printed as if they are current.
+        {
+            let target_health = target_health.lock().unwrap();
+            statistics.retain_hosts(|hostname| !target_health.get(hostname).map(|health| health.failed()).unwrap_or(false));
+        }
+        let node_exporter_values = parse_node_exporter_map(&raw_node_exporter_values, &mut sample_errors);
+        process_statistics(&node_exporter_values, &mut statistics, &mut sample_errors, outputs.contains(&OutputOptions::SarI)).await;
+        exit_state.sample_errors.lock().unwrap().clone_from(&sample_errors);
+        historical_data.lock().unwrap().add(&statistics);
+
+        // % instead of is_multiple_of, which needs rust 1.87.
+        #[allow(clippy::manual_is_multiple_of)]
+        print_outputs(&outputs, &view_options, &statistics, &mut exit_state.averages.lock().unwrap(), row_writer.as_ref(), exporter.as_ref(), print_counter == 0 || print_counter % args.header_print == 0);
+        match &row_writer
+        {
+            Some(row_writer) => {
+                let mut row_writer = row_writer.lock().unwrap();
+                write_counter_resets(&statistics, &mut row_writer)
+                    .and_then(|_| write_failed_targets(&target_health.lock().unwrap(), report_never_succeeded, &mut row_writer))
+                    .and_then(|_| row_writer.flush())
+                    .unwrap_or_else(|error| error!("{:#}", error));
+            },
+            None => {
+                print_counter_resets(&statistics);
+                print_failed_targets(&target_health.lock().unwrap(), report_never_succeeded);
+            },
         }
         print_counter += 1;
 
+        // the ticks that are missed because an interval took too long are skipped, so the output has gaps.
+        // with a short interval this can happen often, so it's reported at most once a minute.
+        let interval_duration = interval_start.elapsed();
+        if interval_duration > args.interval
+        {
+            overruns += 1;
+            if last_overrun_warning.map(|last_warning| last_warning.elapsed() >= Duration::from_secs(60)).unwrap_or(true)
+            {
+                warn!("scraping and processing took {:?}, which is longer than the interval of {:?}: intervals are skipped ({} overrun(s) so far)", interval_duration, args.interval, overruns);
+                last_overrun_warning = Some(time::Instant::now());
+            }
+        }
+
+        // the first interval only sets the baseline, so count intervals with values take count + 1 scrapes, like sar.
+        // with a duration or until time, the interval that would end after it is not started.
+        if args.count.map(|count| print_counter > count).unwrap_or(false) || end.map(|end| interval_start + args.interval > end).unwrap_or(false)
+        {
+            break;
+        }
+    }
+    finish(&exit_state, false);
+}
+
+// the replay reads the intervals from the archive(s) as fast as it can, instead of waiting for the interval.
+async fn replay(
+    args: &Opts,
+) -> !
+{
+    let mut statistics: Statistics = Default::default();
+    let historical_data : Arc<Mutex<HistoricalData>> = Arc::new(Mutex::new(HistoricalData::new(per_cpu_history(args))));
+    // the last interval outside the start/end window is kept, so the first interval inside the window has values to calculate the difference with.
+    let mut baseline: Option<ArchiveInterval> = None;
+    // the archive doesn't store the interval it was recorded with: the shortest distance between two intervals is taken as the interval,
+    // and intervals further apart than that (dsar was stopped, or only a time window was recorded every day) take a new baseline.
+    let mut recording_interval: Option<chrono::Duration> = None;
+    let mut previous_timestamp: Option<DateTime<Utc>> = None;
+    let mut last_processed: Option<DateTime<Utc>> = None;
+    let mut sample_errors = SampleErrors::default();
+    let outputs = unique_outputs(&args.output);
+    let view_options = ViewOptions { cpus: args.cpus.clone(), irq_top: args.irq_top, ..Default::default() };
+    let mut print_counter: u64 = 0;
+    let mut averages = vec![Averages::default(); outputs.len()];
+    let row_writer = create_row_writer(args);
+
+    for file in args.replay.as_ref().unwrap().split(',')
+    {
+        let mut archive = ArchiveReader::new(file).unwrap_or_else(|error| {
+            error!("{:#}", error);
+            process::exit(1);
+        }).peekable();
+        while let Some(interval) = archive.next()
+        {
+            let interval = match interval
+            {
+                Ok(interval) => interval,
+                Err(error) => {
+                    warn!("{:#}, skipping the rest of the archive", error);
+                    break;
+                },
+            };
+            if let Some(previous_timestamp) = previous_timestamp.replace(interval.timestamp)
+            {
+                let distance = interval.timestamp.signed_duration_since(previous_timestamp);
+                if distance > chrono::Duration::zero() && recording_interval.map(|recording_interval| distance < recording_interval).unwrap_or(true)
+                {
+                    recording_interval = Some(distance);
+                }
+            }
+            if args.start.map(|start| start.is_after(interval.timestamp)).unwrap_or(false) || args.end.map(|end| end.is_before(interval.timestamp)).unwrap_or(false)
+            {
+                baseline = Some(interval);
+                continue;
+            }
+            for interval in baseline.take().iter().chain(std::iter::once(&interval))
+            {
+                if is_gap(last_processed, interval.timestamp, recording_interval)
+                {
+                    debug!("gap in the archive before {}, taking a new baseline", interval.timestamp);
+                    statistics = Default::default();
+                }
+                process_archive_interval(interval, &mut statistics, &mut sample_errors, outputs.contains(&OutputOptions::SarI)).await;
+                last_processed = Some(interval.timestamp);
+            }
+            historical_data.lock().unwrap().add(&statistics);
+
+            // an archive recorded with a sub-second interval is shown with milliseconds, with a new header for the wider time column.
+            if !subsecond_time() && matches!(archive.peek(), Some(Ok(next_interval)) if next_interval.timestamp.signed_duration_since(interval.timestamp) < chrono::Duration::milliseconds(950))
+            {
+                set_subsecond_time(true);
+                print_counter = 0;
+            }
+
+            #[allow(clippy::manual_is_multiple_of)]
+            print_outputs(&outputs, &view_options, &statistics, &mut averages, row_writer.as_ref(), None, print_counter == 0 || print_counter % args.header_print == 0);
+            match &row_writer
+            {
+                Some(row_writer) => {
+                    let mut row_writer = row_writer.lock().unwrap();
+                    write_counter_resets(&statistics, &mut row_writer)
+                        .and_then(|_| row_writer.flush())
+                        .unwrap_or_else(|error| error!("{:#}", error));
+                },
+                None => print_counter_resets(&statistics),
+            }
+            print_counter += 1;
+        }
+    }
+    finish(&ExitState {
+        historical_data,
+        target_health: Default::default(),
+        sample_errors: Arc::new(Mutex::new(sample_errors)),
+        report_never_succeeded: false,
+        graph: args.graph,
+        save_history: args.save_history.clone(),
+        csv: args.csv.clone(),
+        outputs,
+        view_options,
+        averages: Arc::new(Mutex::new(averages)),
+        minmax: args.minmax,
+        text_summaries: row_writer.is_none() || args.output_file.is_some(),
+        row_writer,
+    }, false);
+}
+
+// more than one and a half interval between two processed intervals is a gap: a delta over it would be an average over the gap.
+fn is_gap(
+    last_processed: Option<DateTime<Utc>>,
+    timestamp: DateTime<Utc>,
+    recording_interval: Option<chrono::Duration>,
+) -> bool
+{
+    match (last_processed, recording_interval)
+    {
+        (Some(last_processed), Some(recording_interval)) => timestamp.signed_duration_since(last_processed) > recording_interval * 3 / 2,
+        _ => false,
+    }
+}
+
+// the archive only has the scrapes that succeeded: like the live run, the statistics of a host that is not in the interval are removed,
+// so its last values are not printed again.
+async fn process_archive_interval(
+    interval: &ArchiveInterval,
+    statistics: &mut Statistics,
+    sample_errors: &mut SampleErrors,
+    interrupts: bool,
+)
+{
+    statistics.retain_hosts(|hostname| interval.raw_scrapes.contains_key(hostname));
+    let scrapes = parse_node_exporter_map(&interval.raw_scrapes, sample_errors);
+    process_statistics(&scrapes, statistics, sample_errors, interrupts).await;
+}
+
+fn create_targets(
+    args: &Opts,
+) -> anyhow::Result<Vec<Target>>
+{
+    if let Some(targets) = &args.targets
+    {
+        targets.split(',').map(|target| Target::parse(target, &args.scheme)).collect()
+    }
+    else
+    {
+        targets_from_hosts_ports(&args.hosts.split(',').collect::<Vec<_>>(), &args.ports.split(',').collect::<Vec<_>>(), &args.scheme)
+    }
+}
+
+// the labels of the targets from a file_sd file, by the name of the target, which is the hostname of the rows.
+fn create_target_labels(
+    targets: &[Target],
+) -> BTreeMap<String, BTreeMap<String, String>>
+{
+    targets.iter().filter(|target| !target.labels.is_empty()).map(|target| (target.name(), target.labels.clone())).collect()
+}
+
+fn create_scrape_options(
+    args: &Opts,
+) -> anyhow::Result<ScrapeOptions>
+{
+    let ca_certificate = match &args.cacert
+    {
+        Some(file) => Some(reqwest::Certificate::from_pem(&fs::read(file).with_context(|| format!("reading CA certificate {}", file))?)?),
+        None => None,
+    };
+    let identity = match (&args.cert, &args.key)
+    {
+        (Some(cert), Some(key)) => {
+            let certificate = fs::read(cert).with_context(|| format!("reading client certificate {}", cert))?;
+            let private_key = fs::read(key).with_context(|| format!("reading client key {}", key))?;
+            Some(reqwest::Identity::from_pkcs8_pem(&certificate, &private_key)?)
+        },
+        _ => None,
+    };
+    Ok(ScrapeOptions {
+        ca_certificate,
+        insecure_skip_verify: args.insecure_skip_verify,
+        identity,
+        username: args.username.clone(),
+        password: read_secret(args.password_file.as_deref(), args.password_env.as_deref())?,
+        bearer_token: read_secret(args.bearer_token_file.as_deref(), args.bearer_token_env.as_deref())?,
+    })
+}
+
+fn plot(
+    file: &str,
+    args: &Opts,
+)
+{
+    let mut historical_data = HistoricalData::load(file).unwrap_or_else(|error| {
+        error!("{:#}", error);
+        process::exit(1);
+    });
+    historical_data.retain(args.start, args.end, args.host_filter.as_ref().map(|hosts| hosts.split(',').collect()).as_ref());
+    let historical_data = Arc::new(Mutex::new(historical_data));
+    create_plots(&historical_data);
+    export_csv(args.csv.as_deref(), &historical_data);
+}
+
+// the cpus separately are only kept for the cpu heatmap, and with all details when the history is saved or exported.
+fn per_cpu_history(
+    args: &Opts,
+) -> PerCpuHistory
+{
+    if args.save_history.is_some() || args.csv.is_some() { PerCpuHistory::Details }
+    else if args.graph { PerCpuHistory::Busy }
+    else { PerCpuHistory::None }
+}
+
+fn save_history(
+    file: Option<&str>,
+    historical_data: &Arc<Mutex<HistoricalData>>,
+)
+{
+    if let Some(file) = file
+    {
+        historical_data.lock().unwrap().save(file).unwrap_or_else(|error| error!("{:#}", error));
+    }
+}
+
+fn export_csv(
+    prefix: Option<&str>,
+    historical_data: &Arc<Mutex<HistoricalData>>,
+)
+{
+    if let Some(prefix) = prefix
+    {
+        match historical_data.lock().unwrap().export_csv(prefix)
+        {
+            Ok(files) => info!("exported the historical data to {}", files.join(", ")),
+            Err(error) => error!("{:#}", error),
+        }
+    }
+}
+
+/// What the rows of the views are made with, besides the statistics: the cpus to show separately, the number of interrupts to show,
+/// and the labels of the targets.
+#[derive(Clone, Default)]
+struct ViewOptions {
+    cpus: Option<CpuList>,
+    irq_top: Option<usize>,
+    target_labels: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// What is reported and saved at exit, shared with the ctrl-c handler.
+#[derive(Clone)]
+struct ExitState {
+    historical_data: Arc<Mutex<HistoricalData>>,
+    target_health: Arc<Mutex<BTreeMap<String, TargetHealth>>>,
+    sample_errors: Arc<Mutex<SampleErrors>>,
+    report_never_succeeded: bool,
+    graph: bool,
+    save_history: Option<String>,
+    csv: Option<String>,
+    outputs: Vec<OutputOptions>,
+    view_options: ViewOptions,
+    averages: Arc<Mutex<Vec<Averages>>>,
+    minmax: bool,
+    row_writer: Option<Arc<Mutex<RowWriter>>>,
+    // with json rows on stdout, the summaries that are text are left out.
+    text_summaries: bool,
+}
+
+// the end of a run, by count, duration or until, by ctrl-c, or the end of a replay.
+// the exit code is 130 when interrupted, 2 when none of the targets could be scraped, and 0 otherwise.
+fn finish(
+    exit_state: &ExitState,
+    interrupted: bool,
+) -> !
+{
+    // ctrl-c during the finish of a run that ended by itself waits here, until the process exits.
+    static FINISHING: Mutex<()> = Mutex::new(());
+    let _finishing = FINISHING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let averages = exit_state.averages.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (output, averages) in exit_state.outputs.iter().zip(averages.iter()).filter(|(_, averages)| !averages.is_empty())
+    {
+        match &exit_state.row_writer
+        {
+            Some(row_writer) => {
+                let mut row_writer = row_writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let view = view(*output, &exit_state.view_options);
+                for (summary, hostname, label, labels, values) in averages.summary(exit_state.minmax)
+                {
+                    row_writer.write_summary(&view, summary, hostname, label, labels, &values).unwrap_or_else(|error| error!("{:#}", error));
+                }
+            },
+            None => {
+                println!();
+                print_header(*output, &exit_state.view_options);
+                averages.print(exit_state.minmax, |hostname, time, label, labels, values| print_row(*output, &exit_state.view_options, hostname, time, label, labels, values));
+            },
+        }
+    }
+    if let Some(row_writer) = &exit_state.row_writer
+    {
+        row_writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).finish().unwrap_or_else(|error| error!("{:#}", error));
+    }
+    let target_health = exit_state.target_health.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !target_health.is_empty() && exit_state.text_summaries
+    {
+        println!();
+        print_target_health_summary(&target_health, exit_state.report_never_succeeded);
+    }
+    if exit_state.text_summaries
+    {
+        print_sample_errors(&exit_state.sample_errors.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    }
+    if exit_state.graph
+    {
+        create_plots(&exit_state.historical_data);
+    };
+    save_history(exit_state.save_history.as_deref(), &exit_state.historical_data);
+    export_csv(exit_state.csv.as_deref(), &exit_state.historical_data);
+    io::stdout().flush().unwrap_or_else(|error| error!("flushing output: {}", error));
+
+    let exit_code = if interrupted
+    {
+        130
+    }
+    else if !target_health.is_empty() && target_health.values().all(|health| health.last_success.is_none())
+    {
+        2
+    }
+    else
+    {
+        0
+    };
+    process::exit(exit_code);
+}
+
+fn print_sample_errors(
+    sample_errors: &SampleErrors,
+)
+{
+    if !sample_errors.is_empty()
+    {
+        println!();
+        sample_errors.print_summary();
+    }
+}
+
+fn create_plots(
+    historical_data: &Arc<Mutex<HistoricalData>>,
+)
+{
+    create_cpu_plots(historical_data);
+    create_cpu_heatmap_plots(historical_data);
+    create_disk_plots(historical_data);
+    create_memory_plots(historical_data);
+    create_yb_memory_plots(historical_data);
+    create_yb_io_plots(historical_data);
+    create_misc_plots(historical_data);
+    create_network_plots(historical_data);
+    create_netstat_plots(historical_data);
+    create_vmstat_plots(historical_data);
+}
+
+/// The views in the order they are given, each once.
+fn unique_outputs(
+    outputs: &[OutputOptions],
+) -> Vec<OutputOptions>
+{
+    outputs.iter().copied().unique().collect()
+}
+
+// with more than one view, every view is printed as a block with its own header, separated by an empty line, like sar does.
+// with a row writer, the rows are written as json instead, without headers.
+fn print_outputs(
+    outputs: &[OutputOptions],
+    view_options: &ViewOptions,
+    statistics: &Statistics,
+    averages: &mut [Averages],
+    row_writer: Option<&Arc<Mutex<RowWriter>>>,
+    exporter: Option<&Exporter>,
+    header: bool,
+)
+{
+    let mut exported_views = Vec::new();
+    for (output, averages) in outputs.iter().zip(averages.iter_mut())
+    {
+        let mut rows = output_rows(*output, view_options, statistics);
+        for row in rows.iter_mut()
+        {
+            if let Some(labels) = view_options.target_labels.get(&row.hostname)
+            {
+                row.labels.extend(labels.iter().map(|(name, value)| (name.clone(), value.clone())));
+            }
+            averages.add(&row.hostname, &row.label, &row.labels, &row.values);
+        }
+        if let Some(row_writer) = row_writer
+        {
+            let mut row_writer = row_writer.lock().unwrap();
+            let view = view(*output, view_options);
+            for row in &rows
+            {
+                row_writer.write_row(&view, row).unwrap_or_else(|error| error!("{:#}", error));
+            }
+        }
+        else
+        {
+            if outputs.len() > 1
+            {
+                println!();
+                print_header(*output, view_options);
+            }
+            else if header
+            {
+                print_header(*output, view_options);
+            }
+            for row in &rows
+            {
+                print_row(*output, view_options, &row.hostname, &format_time(row.timestamp), &row.label, &row.labels, &row.values);
+            }
+        }
+        if exporter.is_some()
+        {
+            exported_views.push((view(*output, view_options), rows));
+        }
+    }
+    if let Some(exporter) = exporter
+    {
+        exporter.update(&exported_views);
+    }
+    if let Some(row_writer) = row_writer
+    {
+        row_writer.lock().unwrap().flush().unwrap_or_else(|error| error!("{:#}", error));
+    }
+}
+
+fn create_row_writer(
+    args: &Opts,
+) -> Option<Arc<Mutex<RowWriter>>>
+{
+    if args.format == OutputFormat::Text
+    {
+        if args.output_file.is_some()
+        {
+            error!("--output-file needs --format json or ndjson");
+            process::exit(1);
+        }
+        return None;
+    }
+    let row_writer = RowWriter::new(args.format, args.output_file.as_deref()).unwrap_or_else(|error| {
+        error!("{:#}", error);
+        process::exit(1);
+    });
+    Some(Arc::new(Mutex::new(row_writer)))
+}
+
+fn print_header(
+    output: OutputOptions,
+    view_options: &ViewOptions,
+)
+{
+    let cpus = view_options.cpus.as_ref();
+    match output {
+        OutputOptions::SarU => print_sar_u_header("normal", cpus),
+        OutputOptions::SarUAll => print_sar_u_header("all", cpus),
+        OutputOptions::SarD => print_sar_d_header(),
+        OutputOptions::SarS => print_sar_s_header(),
+        OutputOptions::SarW => print_sar_w_header(),
+        OutputOptions::SarB => print_sar_b_header(),
+        OutputOptions::SarQ => print_sar_q_header(),
+        OutputOptions::SarI => print_sar_i_header(cpus),
+        OutputOptions::SarNDev => print_sar_n_dev_header(),
+        OutputOptions::SarNEdev => print_sar_n_edev_header(),
+        OutputOptions::SarNSock => print_sar_n_sock_header(),
+        OutputOptions::SarNSock6 => print_sar_n_sock6_header(),
+        OutputOptions::SarNSoft => print_sar_n_soft_header(),
+        OutputOptions::SarNTcp => print_sar_n_tcp_header(),
+        OutputOptions::SarNEtcp => print_sar_n_etcp_header(),
+        OutputOptions::SarNUdp => print_sar_n_udp_header(),
+        OutputOptions::SarNIp => print_sar_n_ip_header(),
+        OutputOptions::SarNEip => print_sar_n_eip_header(),
+        OutputOptions::TcpListen => print_tcp_listen_header(),
+        OutputOptions::SarR => print_sar_r_header("normal"),
+        OutputOptions::SarRAll => print_sar_r_header("all"),
+        OutputOptions::Iostat => print_iostat_header(),
+        OutputOptions::IostatX => print_iostat_x_header(),
+        OutputOptions::YbCpu => print_yb_cpu_header(),
+        OutputOptions::YbNetwork => print_yb_network_header(),
+        OutputOptions::YbMemory => print_yb_memory_header(),
+        OutputOptions::YbIo => print_yb_io_header(),
+        OutputOptions::CpuAll => print_sar_u_header("extended", cpus),
+        OutputOptions::XfsIops => print_xfs_iops_header(),
+        OutputOptions::MemRelevant => print_sar_r_header("relevant"),
+        OutputOptions::Vmstat => print_vmstat_header(),
+        OutputOptions::Psi => print_psi_header(),
+    }
+}
+
+fn output_rows(
+    output: OutputOptions,
+    view_options: &ViewOptions,
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let cpus = view_options.cpus.as_ref();
+    match output {
+        OutputOptions::SarU => sar_u_rows("normal", cpus, statistics),
+        OutputOptions::SarUAll => sar_u_rows("all", cpus, statistics),
+        OutputOptions::SarD => sar_d_rows(statistics),
+        OutputOptions::SarS => sar_s_rows(statistics),
+        OutputOptions::SarW => sar_w_rows(statistics),
+        OutputOptions::SarB => sar_b_rows(statistics),
+        OutputOptions::SarQ => sar_q_rows(statistics),
+        OutputOptions::SarI => sar_i_rows(cpus, view_options.irq_top, statistics),
+        OutputOptions::SarNDev => sar_n_dev_rows(statistics),
+        OutputOptions::SarNEdev => sar_n_edev_rows(statistics),
+        OutputOptions::SarNSock => sar_n_sock_rows(statistics),
+        OutputOptions::SarNSock6 => sar_n_sock6_rows(statistics),
+        OutputOptions::SarNSoft => sar_n_soft_rows(statistics),
+        OutputOptions::SarNTcp => sar_n_tcp_rows(statistics),
+        OutputOptions::SarNEtcp => sar_n_etcp_rows(statistics),
+        OutputOptions::SarNUdp => sar_n_udp_rows(statistics),
+        OutputOptions::SarNIp => sar_n_ip_rows(statistics),
+        OutputOptions::SarNEip => sar_n_eip_rows(statistics),
+        OutputOptions::TcpListen => tcp_listen_rows(statistics),
+        OutputOptions::SarR => sar_r_rows("normal", statistics),
+        OutputOptions::SarRAll => sar_r_rows("all", statistics),
+        OutputOptions::Iostat => iostat_rows(statistics),
+        OutputOptions::IostatX => iostat_x_rows(statistics),
+        OutputOptions::YbCpu => yb_cpu_rows(statistics),
+        OutputOptions::YbNetwork => yb_network_rows(statistics),
+        OutputOptions::YbMemory => yb_memory_rows(statistics),
+        OutputOptions::YbIo => yb_io_rows(statistics),
+        OutputOptions::CpuAll => sar_u_rows("extended", cpus, statistics),
+        OutputOptions::XfsIops => xfs_iops_rows(statistics),
+        OutputOptions::MemRelevant => sar_r_rows("relevant", statistics),
+        OutputOptions::Vmstat => vmstat_rows(statistics),
+        OutputOptions::Psi => psi_rows(statistics),
+    }
+}
+
+fn print_row(
+    output: OutputOptions,
+    view_options: &ViewOptions,
+    hostname: &str,
+    time: &str,
+    label: &str,
+    labels: &Labels,
+    values: &[f64],
+)
+{
+    let cpus = view_options.cpus.as_ref();
+    match output {
+        OutputOptions::SarU => print_sar_u_row("normal", cpus, hostname, time, label, values),
+        OutputOptions::SarUAll => print_sar_u_row("all", cpus, hostname, time, label, values),
+        OutputOptions::SarD => print_sar_d_row(hostname, time, label, values),
+        OutputOptions::SarS => print_sar_s_row(hostname, time, values),
+        OutputOptions::SarW => print_sar_w_row(hostname, time, values),
+        OutputOptions::SarB => print_sar_b_row(hostname, time, values),
+        OutputOptions::SarQ => print_sar_q_row(hostname, time, values),
+        OutputOptions::SarI => print_sar_i_row(cpus, hostname, time, label, labels, values),
+        OutputOptions::SarNDev => print_sar_n_dev_row(hostname, time, label, values),
+        OutputOptions::SarNEdev => print_sar_n_edev_row(hostname, time, label, values),
+        OutputOptions::SarNSock => print_sar_n_sock_row(hostname, time, values),
+        OutputOptions::SarNSock6 => print_sar_n_sock6_row(hostname, time, values),
+        OutputOptions::SarNSoft => print_sar_n_soft_row(hostname, time, values),
+        OutputOptions::SarNTcp => print_sar_n_tcp_row(hostname, time, values),
+        OutputOptions::SarNEtcp => print_sar_n_etcp_row(hostname, time, values),
+        OutputOptions::SarNUdp => print_sar_n_udp_row(hostname, time, values),
+        OutputOptions::SarNIp => print_sar_n_ip_row(hostname, time, values),
+        OutputOptions::SarNEip => print_sar_n_eip_row(hostname, time, values),
+        OutputOptions::TcpListen => print_tcp_listen_row(hostname, time, values),
+        OutputOptions::SarR => print_sar_r_row("normal", hostname, time, values),
+        OutputOptions::SarRAll => print_sar_r_row("all", hostname, time, values),
+        OutputOptions::Iostat => print_iostat_row(hostname, time, label, values),
+        OutputOptions::IostatX => print_iostat_x_row(hostname, time, label, values),
+        OutputOptions::YbCpu => print_yb_cpu_row(hostname, time, values),
+        OutputOptions::YbNetwork => print_yb_network_row(hostname, time, values),
+        OutputOptions::YbMemory => print_yb_memory_row(hostname, time, values),
+        OutputOptions::YbIo => print_yb_io_row(hostname, time, values),
+        OutputOptions::CpuAll => print_sar_u_row("extended", cpus, hostname, time, label, values),
+        OutputOptions::XfsIops => print_xfs_iops_row(hostname, time, label, values),
+        OutputOptions::MemRelevant => print_sar_r_row("relevant", hostname, time, values),
+        OutputOptions::Vmstat => print_vmstat_row(hostname, time, values),
+        OutputOptions::Psi => print_psi_row(hostname, time, values),
+    }
+}
+
+/// The name, label and field names of a view, for the machine readable output.
+fn view(
+    output: OutputOptions,
+    view_options: &ViewOptions,
+) -> View
+{
+    let cpus = view_options.cpus.as_ref();
+    let (label_name, fields) = match output {
+        OutputOptions::SarU => (Some("cpu"), SAR_U_FIELDS),
+        OutputOptions::SarUAll => (Some("cpu"), SAR_U_ALL_FIELDS),
+        OutputOptions::SarD => (Some("device"), SAR_D_FIELDS),
+        OutputOptions::SarS => (None, SAR_S_FIELDS),
+        OutputOptions::SarW => (None, SAR_W_FIELDS),
+        OutputOptions::SarB => (None, SAR_B_FIELDS),
+        OutputOptions::SarQ => (None, SAR_Q_FIELDS),
+        OutputOptions::SarI => (Some("irq"), SAR_I_FIELDS),
+        OutputOptions::SarNDev => (Some("interface"), SAR_N_DEV_FIELDS),
+        OutputOptions::SarNEdev => (Some("interface"), SAR_N_EDEV_FIELDS),
+        OutputOptions::SarNSock => (None, SAR_N_SOCK_FIELDS),
+        OutputOptions::SarNSock6 => (None, SAR_N_SOCK6_FIELDS),
+        OutputOptions::SarNSoft => (None, SAR_N_SOFT_FIELDS),
+        OutputOptions::SarNTcp => (None, SAR_N_TCP_FIELDS),
+        OutputOptions::SarNEtcp => (None, SAR_N_ETCP_FIELDS),
+        OutputOptions::SarNUdp => (None, SAR_N_UDP_FIELDS),
+        OutputOptions::SarNIp => (None, SAR_N_IP_FIELDS),
+        OutputOptions::SarNEip => (None, SAR_N_EIP_FIELDS),
+        OutputOptions::TcpListen => (None, TCP_LISTEN_FIELDS),
+        OutputOptions::SarR => (None, SAR_R_FIELDS),
+        OutputOptions::SarRAll => (None, SAR_R_ALL_FIELDS),
+        OutputOptions::Iostat => (Some("device"), IOSTAT_FIELDS),
+        OutputOptions::IostatX => (Some("device"), IOSTAT_X_FIELDS),
+        OutputOptions::YbCpu => (None, YB_CPU_FIELDS),
+        OutputOptions::YbNetwork => (None, YB_NETWORK_FIELDS),
+        OutputOptions::YbMemory => (None, YB_MEMORY_FIELDS),
+        OutputOptions::YbIo => (None, YB_IO_FIELDS),
+        OutputOptions::CpuAll => (cpus.map(|_| "cpu"), CPU_ALL_FIELDS),
+        OutputOptions::XfsIops => (Some("device"), XFS_IOPS_FIELDS),
+        OutputOptions::MemRelevant => (None, MEM_RELEVANT_FIELDS),
+        OutputOptions::Vmstat => (None, VMSTAT_FIELDS),
+        OutputOptions::Psi => (None, PSI_FIELDS),
+    };
+    View {
+        name: output.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default(),
+        label_name,
+        fields,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::TimeZone;
+    use dsar::RawScrape;
+
+    const SCRAPE: &str = "# TYPE node_load1 gauge\nnode_load1 1.5\n# TYPE node_load5 gauge\nnode_load5 1.2\n# TYPE node_load15 gauge\nnode_load15 1\n";
+
+    fn archive_interval(
+        timestamp: DateTime<Utc>,
+        hostnames: &[&str],
+    ) -> ArchiveInterval
+    {
+        let raw_scrapes = hostnames.iter()
+            .map(|hostname| (hostname.to_string(), RawScrape { timestamp, data: SCRAPE.to_string() }))
+            .collect::<HashMap<_, _>>();
+        ArchiveInterval { timestamp, raw_scrapes }
+    }
+
+    #[tokio::test]
+    async fn replay_drops_a_host_missing_from_an_interval() {
+        let start = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let mut statistics = Statistics::default();
+        let mut sample_errors = SampleErrors::default();
+        process_archive_interval(&archive_interval(start, &["node1:9100:metrics", "node2:9100:metrics"]), &mut statistics, &mut sample_errors, false).await;
+        assert_eq!(output_rows(OutputOptions::SarQ, &ViewOptions::default(), &statistics).len(), 2);
+
+        // node2 failed to be scraped in the next interval, so it is not in the archive.
+        let next = start + chrono::Duration::seconds(1);
+        process_archive_interval(&archive_interval(next, &["node1:9100:metrics"]), &mut statistics, &mut sample_errors, false).await;
+        let rows = output_rows(OutputOptions::SarQ, &ViewOptions::default(), &statistics);
+        assert_eq!(rows.iter().map(|row| (row.hostname.as_str(), row.timestamp)).collect::<Vec<_>>(), vec![("node1:9100:metrics", next)]);
+    }
+
+    #[test]
+    fn replay_gap() {
+        let start = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let interval = Some(chrono::Duration::seconds(5));
+        assert!(!is_gap(None, start, interval));
+        assert!(!is_gap(Some(start), start + chrono::Duration::seconds(7), interval));
+        assert!(is_gap(Some(start), start + chrono::Duration::seconds(8), interval));
+        assert!(!is_gap(Some(start), start + chrono::Duration::seconds(60), None));
     }
 }
@@ -1,13 +1,14 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT};
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NodeCpuDetails {
     pub user: f64,
     pub nice: f64,
@@ -23,26 +24,30 @@ pub struct NodeCpuDetails {
     pub schedstat_wait: f64,
 }
 
+impl NodeCpuDetails {
+    /// Busy is everything but idle and iowait, as a percentage of the total time; none if there is no time.
+    pub fn busy_percent(&self) -> Option<f64> {
+        let total = self.user + self.nice + self.system + self.iowait + self.steal + self.irq + self.softirq + self.guest_user + self.guest_nice + self.idle;
+        (total > 0.).then(|| (total - self.idle - self.iowait) / total * 100.)
+    }
+}
+
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     match sample.metric.as_str()
     {
         "node_schedstat_running_seconds_total" |
         "node_schedstat_waiting_seconds_total" => {
-            let Value::Counter(value) = sample.value else { panic!("{} value enum type should be Counter!", sample.metric) };
-            let cpu_number = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).unwrap();
+            let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
+            let cpu_number = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "cpu" })?;
             statistics
-                .entry((hostname.to_string(), sample.metric.clone(), cpu_number.to_string(), "".to_string()))
+                .entry(hostname, &sample.metric, cpu_number, "")
                 .and_modify(|row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
-                    row.first_value = false;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{} cpu: {}: last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", sample.metric, cpu_number, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 })
                 .or_insert(
@@ -57,17 +62,13 @@ pub fn process_statistic(
         },
         "node_cpu_seconds_total" |
         "node_cpu_guest_seconds_total" => {
-            let Value::Counter(value) = sample.value else { panic!("{} value enum type should be Counter!", sample.metric) };
-            let cpu_number = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).unwrap();
-            let mode = sample.labels.iter().find(|(label, _)| *label == "mode").map(|(_, value)| value).unwrap();
+            let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
+            let cpu_number = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "cpu" })?;
+            let mode = sample.labels.iter().find(|(label, _)| *label == "mode").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "mode" })?;
             statistics
-                .entry((hostname.to_string(), sample.metric.clone(), cpu_number.to_string(), mode.to_string()))
+                .entry(hostname, &sample.metric, cpu_number, mode)
                 .and_modify(|row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
-                    row.first_value = false;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{} mode: {}, cpu: {}: last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", sample.metric, mode, cpu_number, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 })
                 .or_insert(
@@ -84,120 +85,232 @@ pub fn process_statistic(
         },
         &_ => {},
     }
+    Ok(())
 }
 
 pub fn create_total(
-    sample: &Sample,
-    host: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
+    metric: &str,
+    hostname: &str,
+    statistics: &mut Statistics,
 )
 {
-    match sample.metric.as_str()
+    match metric
     {
         "node_schedstat_running_seconds_total" |
-        "node_schedstat_waiting_seconds_total" => {
-            let last_timestamp = statistics.iter().find(|((hostname, metric, cpu, _), _)| hostname == host && metric == &sample.metric && cpu != "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            let per_second_value = statistics.iter().filter(|((hostname, metric, cpu, _), _)| hostname == host && metric == &sample.metric && cpu != "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let first_val = statistics.iter().find(|((hostname, metric, cpu, _), _)| hostname == host && metric == &sample.metric && cpu != "total").map(|((_, _, _, _), statistic)| statistic.first_value).unwrap();
-            statistics.entry((host.to_string(), sample.metric.to_string(), "total".to_string(), "".to_string()))
-                    .and_modify(|row| { row.per_second_value = per_second_value; row.last_timestamp = last_timestamp; row.first_value = first_val; })
-                    .or_insert(Statistic { per_second_value, last_timestamp, first_value: first_val, ..Default::default() });
-        },
+        "node_schedstat_waiting_seconds_total" |
         "node_cpu_seconds_total" |
-        "node_cpu_guest_seconds_total" => {
-            let mode = sample.labels.iter().find(|(label, _)| *label == "mode").map(|(_, value)| value).unwrap();
-            let last_timestamp = statistics.iter().find(|((hostname, metric, cpu, run_mode), _)| hostname == host && metric == &sample.metric && cpu != "total" && run_mode == mode).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            let per_second_value = statistics.iter().filter(|((hostname, metric, cpu, run_mode), _)| hostname == host && metric == &sample.metric && cpu != "total" && run_mode == mode).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let first_val = statistics.iter().find(|((hostname, metric, cpu, run_mode), _)| hostname == host && metric == &sample.metric && cpu != "total" && run_mode == mode).map(|((_, _, _, _), statistic)| statistic.first_value).unwrap();
-            statistics.entry((host.to_string(), sample.metric.to_string(), "total".to_string(), mode.to_string()))
-                    .and_modify(|row| { row.per_second_value = per_second_value; row.last_timestamp = last_timestamp; row.first_value = first_val; })
-                    .or_insert(Statistic { per_second_value, last_timestamp, first_value: first_val, ..Default::default() });
-            //println!("total: {:?}", &statistics.iter().find(|((hostname , metric, cpu_nr , mode_name), statistic)| hostname == host && metric == &sample.metric && cpu_nr == "total" && mode_name == mode).map(|((h, m, c, o), statistic)| (h, m, c, o, statistic)).unwrap());
-        },
+        "node_cpu_guest_seconds_total" => statistics.create_total(hostname, metric),
         &_ => {},
     }
 }
 
-pub fn print_sar_u(
+/// The CPUs to show separately, like sar -P: ALL, or a list of numbers and ranges, such as 0,2-5.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CpuList {
+    All,
+    Ranges(Vec<(u64, u64)>),
+}
+
+impl CpuList {
+    pub fn parse(
+        list: &str,
+    ) -> Result<Self, String>
+    {
+        if list.eq_ignore_ascii_case("all")
+        {
+            return Ok(CpuList::All);
+        }
+        list.split(',')
+            .map(|range| {
+                let (first, last) = range.split_once('-').unwrap_or((range, range));
+                match (first.trim().parse::<u64>(), last.trim().parse::<u64>())
+                {
+                    (Ok(first), Ok(last)) if first <= last => Ok((first, last)),
+                    _ => Err(format!("invalid cpu or range of cpus: {} (use ALL, or numbers and ranges, such as 0,2-5)", range)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(CpuList::Ranges)
+    }
+    pub fn contains(
+        &self,
+        cpu: u64,
+    ) -> bool
+    {
+        match self
+        {
+            CpuList::All => true,
+            CpuList::Ranges(ranges) => ranges.iter().any(|(first, last)| (*first..=*last).contains(&cpu)),
+        }
+    }
+}
+
+// with a cpu list, the sar-u views show the cpus separately, and cpu-all gets a CPU column.
+pub fn sar_u_rows(
     mode: &str,
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+    cpus: Option<&CpuList>,
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_cpu_seconds_total" && !row.first_value)
+        if !statistics.rows(hostname, "node_cpu_seconds_total").any(|(_, row)| !row.first_value)
         {
-            let user_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let system_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "system" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let iowait_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "iowait" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let irq_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "irq" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let nice_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "nice" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let softirq_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "softirq" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let steal_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "steal" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let idle_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "idle" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let guest_user = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_guest_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let guest_nice = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_guest_seconds_total" && mode == "nice" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let schedstat_running = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_schedstat_running_seconds_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let schedstat_waiting = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_schedstat_waiting_seconds_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let total_time = user_time + system_time + iowait_time + irq_time + nice_time + softirq_time + steal_time + idle_time + guest_user + guest_nice;
-            let time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            match mode
+            continue;
+        }
+        // the total as "all", like sar, and with a cpu list the cpus in it, in numeric order.
+        let cpus = match cpus
+        {
+            None => vec!["total".to_string()],
+            Some(cpu_list) => {
+                let cpus = statistics.rows(hostname, "node_cpu_seconds_total")
+                    .filter(|(_, row)| !row.first_value)
+                    .filter_map(|((cpu, _), _)| cpu.parse::<u64>().ok())
+                    .unique()
+                    .filter(|cpu| cpu_list.contains(*cpu))
+                    .sorted()
+                    .map(|cpu| cpu.to_string());
+                if *cpu_list == CpuList::All { std::iter::once("total".to_string()).chain(cpus).collect() } else { cpus.collect() }
+            },
+        };
+        for cpu in cpus
+        {
+            // a cpu, or the total, with a counter reset is a new baseline for this interval.
+            if statistics.get(hostname, "node_cpu_seconds_total", &cpu, "user").map(|statistic| statistic.first_value).unwrap_or(false)
             {
-                "normal" => {
-                    println!("{:30} {:8} {:3} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                             hostname,
-                             time.format("%H:%M:%S"),
-                             "all",
-                             user_time / total_time * 100.,
-                             nice_time / total_time * 100.,
-                             system_time / total_time * 100.,
-                             iowait_time / total_time * 100.,
-                             steal_time / total_time * 100.,
-                             idle_time / total_time * 100.,
-                    );
-                },
-                "all" => {
-                    println!("{:30} {:8} {:3} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                             hostname,
-                             time.format("%H:%M:%S"),
-                             "all",
-                             user_time / total_time * 100.,
-                             nice_time / total_time * 100.,
-                             system_time / total_time * 100.,
-                             iowait_time / total_time * 100.,
-                             steal_time / total_time * 100.,
-                             irq_time / total_time * 100.,
-                             softirq_time / total_time * 100.,
-                             guest_user / total_time * 100.,
-                             guest_nice / total_time * 100.,
-                             idle_time / total_time * 100.,
-                    );
-                },
-                "extended" => {
-                    println!("{:30} {:8} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                             hostname,
-                             time.format("%H:%M:%S"),
-                             user_time,
-                             nice_time,
-                             system_time,
-                             iowait_time,
-                             steal_time,
-                             irq_time,
-                             softirq_time,
-                             guest_user,
-                             guest_nice,
-                             idle_time,
-                             schedstat_running,
-                             schedstat_waiting,
-                    );
-                },
-                &_ => {},
+                continue;
             }
+            let user_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "user").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let system_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "system").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let iowait_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "iowait").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let irq_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "irq").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let nice_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "nice").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let softirq_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "softirq").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let steal_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "steal").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let idle_time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "idle").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let guest_user = statistics.get(hostname, "node_cpu_guest_seconds_total", &cpu, "user").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let guest_nice = statistics.get(hostname, "node_cpu_guest_seconds_total", &cpu, "nice").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let schedstat_running = statistics.first_with(hostname, "node_schedstat_running_seconds_total", &cpu).map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let schedstat_waiting = statistics.first_with(hostname, "node_schedstat_waiting_seconds_total", &cpu).map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let total_time = user_time + system_time + iowait_time + irq_time + nice_time + softirq_time + steal_time + idle_time + guest_user + guest_nice;
+            let time = statistics.get(hostname, "node_cpu_seconds_total", &cpu, "user").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = match mode
+            {
+                "normal" => vec![
+                    user_time / total_time * 100.,
+                    nice_time / total_time * 100.,
+                    system_time / total_time * 100.,
+                    iowait_time / total_time * 100.,
+                    steal_time / total_time * 100.,
+                    idle_time / total_time * 100.,
+                ],
+                "all" => vec![
+                    user_time / total_time * 100.,
+                    nice_time / total_time * 100.,
+                    system_time / total_time * 100.,
+                    iowait_time / total_time * 100.,
+                    steal_time / total_time * 100.,
+                    irq_time / total_time * 100.,
+                    softirq_time / total_time * 100.,
+                    guest_user / total_time * 100.,
+                    guest_nice / total_time * 100.,
+                    idle_time / total_time * 100.,
+                ],
+                "extended" => vec![
+                    user_time,
+                    nice_time,
+                    system_time,
+                    iowait_time,
+                    steal_time,
+                    irq_time,
+                    softirq_time,
+                    guest_user,
+                    guest_nice,
+                    idle_time,
+                    schedstat_running,
+                    schedstat_waiting,
+                ],
+                &_ => continue,
+            };
+            let label = if cpu == "total" { "all".to_string() } else { cpu };
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label, labels: Vec::new(), values });
         }
     }
+    rows
+}
+pub fn print_sar_u_row(
+    mode: &str,
+    cpus: Option<&CpuList>,
+    hostname: &str,
+    time: &str,
+    cpu: &str,
+    values: &[f64],
+)
+{
+    match mode
+    {
+        "normal" => {
+            println!("{:30} {:8} {:3} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+                     hostname,
+                     time,
+                     cpu,
+                     values[0],
+                     values[1],
+                     values[2],
+                     values[3],
+                     values[4],
+                     values[5],
+            );
+        },
+        "all" => {
+            println!("{:30} {:8} {:3} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+                     hostname,
+                     time,
+                     cpu,
+                     values[0],
+                     values[1],
+                     values[2],
+                     values[3],
+                     values[4],
+                     values[5],
+                     values[6],
+                     values[7],
+                     values[8],
+                     values[9],
+            );
+        },
+        "extended" => {
+            // cpu-all has no CPU column, unless the cpus are shown separately.
+            let cpu = if cpus.is_some() { format!(" {:3}", cpu) } else { String::new() };
+            println!("{:30} {:8}{} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+                     hostname,
+                     time,
+                     cpu,
+                     values[0],
+                     values[1],
+                     values[2],
+                     values[3],
+                     values[4],
+                     values[5],
+                     values[6],
+                     values[7],
+                     values[8],
+                     values[9],
+                     values[10],
+                     values[11],
+            );
+        },
+        &_ => {},
+    }
 }
+
+pub const SAR_U_FIELDS: &[&str] = &["usr_percent", "nice_percent", "sys_percent", "iowait_percent", "steal_percent", "idle_percent"];
+pub const SAR_U_ALL_FIELDS: &[&str] = &["usr_percent", "nice_percent", "sys_percent", "iowait_percent", "steal_percent", "irq_percent", "soft_percent", "guest_percent", "gnice_percent", "idle_percent"];
+pub const CPU_ALL_FIELDS: &[&str] = &["usr", "nice", "sys", "iowait", "steal", "irq", "soft", "guest", "gnice", "idle", "sched_running", "sched_waiting"];
 pub fn print_sar_u_header(
    mode: &str,
+   cpus: Option<&CpuList>,
 )
 {
     match mode
@@ -205,7 +318,7 @@ pub fn print_sar_u_header(
         "normal" => {
             println!("{:30} {:8} {:3} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
                      "hostname",
-                     "time",
+                     time_header(),
                      "CPU",
                      "%usr",
                      "%nice",
@@ -218,7 +331,7 @@ pub fn print_sar_u_header(
         "all" => {
             println!("{:30} {:8} {:3} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
                      "hostname",
-                     "time",
+                     time_header(),
                      "CPU",
                      "%usr",
                      "%nice",
@@ -233,9 +346,11 @@ pub fn print_sar_u_header(
             );
         },
         "extended" => {
-            println!("{:30} {:8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            let cpu = if cpus.is_some() { format!(" {:3}", "CPU") } else { String::new() };
+            println!("{:30} {:8}{} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
                      "hostname",
-                     "time",
+                     time_header(),
+                     cpu,
                      "usr",
                      "nice",
                      "sys",
@@ -279,12 +394,12 @@ pub fn create_cpu_plots(
         let low_value: f64 = 0.0;
         let high_value_cpu = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _),_)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.user + row.nice + row.system + row.iowait + row.steal + row.irq + row.softirq + row.guest_user + row.guest_nice + row.idle)
+            .map(|(_, row)| row.user + row.nice + row.system + row.iowait + row.steal + row.irq + row.softirq + row.guest_user + row.guest_nice + row.idle)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_schedstat = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _),_)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.schedstat_runtime + row.schedstat_wait)
+            .map(|(_, row)| row.schedstat_runtime + row.schedstat_wait)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value = vec![high_value_cpu, high_value_schedstat].into_iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
@@ -309,12 +424,12 @@ pub fn create_cpu_plots(
             .unwrap();
         let min_scheduler_wait = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.schedstat_wait)
+            .map(|(_, row)| row.schedstat_wait)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_scheduler_wait = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.schedstat_wait)
+            .map(|(_, row)| row.schedstat_wait)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -327,12 +442,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(1).filled()));
         let min_scheduler_runtime = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.schedstat_runtime)
+            .map(|(_, row)| row.schedstat_runtime)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_scheduler_runtime = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.schedstat_runtime)
+            .map(|(_, row)| row.schedstat_runtime)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -345,12 +460,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(2).filled()));
         let min_guest_nice = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.guest_nice)
+            .map(|(_, row)| row.guest_nice)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_guest_nice = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.guest_nice)
+            .map(|(_, row)| row.guest_nice)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -363,12 +478,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(3).filled()));
         let min_guest_user = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.guest_user)
+            .map(|(_, row)| row.guest_user)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_guest_user = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.guest_user)
+            .map(|(_, row)| row.guest_user)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -381,12 +496,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(4).filled()));
         let min_softirq = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.softirq)
+            .map(|(_, row)| row.softirq)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_softirq = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.softirq)
+            .map(|(_, row)| row.softirq)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -399,12 +514,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(5).filled()));
         let min_irq = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.irq)
+            .map(|(_, row)| row.irq)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_irq = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.irq)
+            .map(|(_, row)| row.irq)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -417,12 +532,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(6).filled()));
         let min_iowait = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.iowait)
+            .map(|(_, row)| row.iowait)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_iowait = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.iowait)
+            .map(|(_, row)| row.iowait)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -435,12 +550,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(8).filled()));
         let min_system = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.system)
+            .map(|(_, row)| row.system)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_system = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.system)
+            .map(|(_, row)| row.system)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -453,12 +568,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(9).filled()));
         let min_nice = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.nice)
+            .map(|(_, row)| row.nice)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_nice = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.nice)
+            .map(|(_, row)| row.nice)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -471,12 +586,12 @@ pub fn create_cpu_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(10).filled()));
         let min_user = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.user)
+            .map(|(_, row)| row.user)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_user = unlocked_historical_data.cpu_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.user)
+            .map(|(_, row)| row.user)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.cpu_details.iter()
@@ -506,3 +621,88 @@ pub fn create_cpu_plots(
     }
 }
 
+
+/// A heatmap per host of how busy every cpu is: the time on the x axis, the cpus on the y axis and the busy percentage as colour.
+/// Busy is everything but idle and iowait, so a core that is saturated by softirqs or by a pinned thread stands out.
+pub fn create_cpu_heatmap_plots(
+    historical_data: &Arc<Mutex<HistoricalData>>,
+)
+{
+    let unlocked_historical_data = historical_data.lock().unwrap();
+    for filter_hostname in unlocked_historical_data.per_cpu_busy.keys().map(|(hostname, _, _)| hostname).unique()
+    {
+        let busy = unlocked_historical_data.per_cpu_busy.iter()
+            .filter(|((hostname, _, _), _)| hostname == filter_hostname)
+            .filter_map(|((_, timestamp, cpu), busy)| cpu.parse::<u64>().ok().map(|cpu| (*timestamp, cpu, *busy)))
+            .collect::<Vec<_>>();
+        let timestamps = busy.iter().map(|(timestamp, _, _)| *timestamp).unique().sorted().collect::<Vec<_>>();
+        // every value is the busy percentage over the recording interval that ends at its timestamp.
+        // the median distance between two samples is the recording interval: it ignores the jitter of the scrapes, and a gap in the recording stays empty instead of stretching the sample after it.
+        let distances = timestamps.iter().tuple_windows().map(|(previous, timestamp)| *timestamp - *previous).sorted().collect::<Vec<_>>();
+        let Some(interval) = distances.get(distances.len() / 2).copied() else { continue };
+        let start_time = timestamps[0] - interval;
+        let end_time = *timestamps.last().unwrap();
+        let last_cpu = busy.iter().map(|(_, cpu, _)| *cpu).max().unwrap();
+        let filename = format!("{}_cpu_heatmap.png", filter_hostname);
+
+        // create the plot
+        let root = BitMapBackend::new(&filename, (1280,900)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let mut contextarea = ChartBuilder::on(&root)
+            .set_label_area_size(LabelAreaPosition::Left, LABEL_AREA_SIZE_LEFT)
+            .set_label_area_size(LabelAreaPosition::Bottom, LABEL_AREA_SIZE_BOTTOM)
+            .set_label_area_size(LabelAreaPosition::Right, LABEL_AREA_SIZE_RIGHT)
+            .caption(format!("CPU busy %, 0 (dark) to 100 (yellow): {}",filter_hostname), (CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE))
+            // an integer range of plotters includes its end, so 0..last_cpu has a segment for every cpu up to and including last_cpu.
+            .build_cartesian_2d(start_time..end_time, (0..last_cpu).into_segmented())
+            .unwrap();
+        contextarea.configure_mesh()
+            .disable_mesh()
+            .x_labels(4)
+            .x_label_formatter(&|x| x.to_rfc3339())
+            .y_labels((last_cpu + 1).min(32) as usize)
+            .y_label_formatter(&|y| match y { SegmentValue::CenterOf(cpu) => cpu.to_string(), _ => String::new() })
+            .y_desc("CPU")
+            .label_style((MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE))
+            .draw()
+            .unwrap();
+        contextarea.draw_series(busy.iter()
+                                    .map(|(timestamp, cpu, busy)| Rectangle::new([(*timestamp - interval, SegmentValue::Exact(*cpu)), (*timestamp, SegmentValue::Exact(cpu + 1))], ViridisRGB::get_color_normalized(busy.clamp(0., 100.), 0., 100.).filled()))
+        )
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_list_parse_ranges() {
+        let cpu_list = CpuList::parse("0, 2-5,7").unwrap();
+        assert_eq!(cpu_list, CpuList::Ranges(vec![(0, 0), (2, 5), (7, 7)]));
+        assert!(cpu_list.contains(0));
+        assert!(!cpu_list.contains(1));
+        assert!(cpu_list.contains(2));
+        assert!(cpu_list.contains(5));
+        assert!(!cpu_list.contains(6));
+        assert!(cpu_list.contains(7));
+        assert!(!cpu_list.contains(8));
+    }
+
+    #[test]
+    fn cpu_list_parse_all() {
+        assert_eq!(CpuList::parse("ALL").unwrap(), CpuList::All);
+        assert_eq!(CpuList::parse("all").unwrap(), CpuList::All);
+        assert!(CpuList::All.contains(1023));
+    }
+
+    #[test]
+    fn cpu_list_parse_invalid() {
+        assert!(CpuList::parse("").is_err());
+        assert!(CpuList::parse("a").is_err());
+        assert!(CpuList::parse("0,").is_err());
+        assert!(CpuList::parse("5-2").is_err());
+        assert!(CpuList::parse("1-x").is_err());
+    }
+}
@@ -1,13 +1,14 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE};
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NodeDiskDetails {
     pub reads_completed_s: f64,
     pub reads_bytes_s: f64,
@@ -29,21 +30,17 @@ pub struct NodeDiskDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
-    let Value::Counter(value) = sample.value else { panic!("{} value enum type should be Counter!", sample.metric) };
-    let device = sample.labels.iter().find(|(label, _)| *label == "device").map(|(_, value)| value).unwrap();
+    let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
+    let device = sample.labels.iter().find(|(label, _)| *label == "device").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "device" })?;
     // do not store device mapper disk statistics
-    if device.starts_with("dm-") { return; };
+    if device.starts_with("dm-") { return Ok(()); };
     statistics
-        .entry((hostname.to_string(), sample.metric.clone(), device.to_string(), "".to_string()))
+        .entry(hostname, &sample.metric, device, "")
         .and_modify(|row| {
-            row.delta_value = value - row.last_value;
-            row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-            row.last_value = value;
-            row.last_timestamp = sample.timestamp;
-            row.first_value = false;
+            row.update_counter(value, sample.timestamp);
             debug!("{} device: {}, last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", sample.metric, device, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
         })
         .or_insert(
@@ -55,15 +52,16 @@ pub fn process_statistic(
                 ..Default::default()
             }
         );
+    Ok(())
 }
 
 pub fn create_total(
-    sample: &Sample,
-    host: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
+    metric: &str,
+    hostname: &str,
+    statistics: &mut Statistics,
 )
 {
-    match sample.metric.as_str()
+    match metric
     {
         "node_disk_read_bytes_total" |
         "node_disk_read_time_seconds_total" |
@@ -80,77 +78,81 @@ pub fn create_total(
         "node_disk_io_time_seconds_total" |
         "node_disk_io_time_weighted_seconds_total" |
         "node_xfs_read_calls_total" |
-        "node_xfs_write_calls_total" => {
-            let last_timestamp = statistics.iter().find(|((hostname, metric, device, _), _)| hostname == host && metric == &sample.metric && device != "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            let per_second_value = statistics.iter().filter(|((hostname, metric, device, _), _)| hostname == host && metric == &sample.metric && device != "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let first_val = statistics.iter().find(|((hostname, metric, device, _), _)| hostname == host && metric == &sample.metric && device != "total").map(|((_, _, _, _), statistic)| statistic.first_value).unwrap();
-            statistics.entry((host.to_string(), sample.metric.to_string(), "total".to_string(), "".to_string()))
-                .and_modify(|row| {
-                    row.per_second_value = per_second_value;
-                    row.last_timestamp = last_timestamp;
-                    row.first_value = first_val;
-                })
-                .or_insert(Statistic
-                {
-                    per_second_value,
-                    last_timestamp,
-                    first_value: first_val,
-                    ..Default::default()
-                });
-        },
+        "node_xfs_write_calls_total" => statistics.create_total(hostname, metric),
         &_ => {},
     }
 }
 
-pub fn print_sar_d(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_d_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_disk_read_bytes_total" && !row.first_value)
+        if statistics.rows(hostname, "node_disk_read_bytes_total").any(|(_, row)| !row.first_value)
         {
-            for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_disk_read_bytes_total").map(|((_, _, device, _), _)| device)
+            for current_device in statistics.rows(hostname, "node_disk_read_bytes_total").map(|((device, _), _)| device)
             {
-                let reads_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let writes_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_writes_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let reads_completed = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let writes_completed = statistics.first_with(hostname, "node_disk_writes_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 let tps = reads_completed + writes_completed;
-                let read_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let write_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_written_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let read_bytes = statistics.first_with(hostname, "node_disk_read_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let write_bytes = statistics.first_with(hostname, "node_disk_written_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 let mut average_read_request_size = read_bytes / reads_completed;
                 average_read_request_size = if average_read_request_size.is_nan() { 0. } else { average_read_request_size };
                 let mut average_write_request_size = write_bytes / writes_completed;
                 average_write_request_size = if average_write_request_size.is_nan() { 0. } else { average_write_request_size };
                 // node_disk_io_time_weighted does not exist for Mac.
-                let queue_size = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_io_time_weighted_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let read_time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let write_time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_write_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let queue_size = statistics.first_with(hostname, "node_disk_io_time_weighted_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let read_time = statistics.first_with(hostname, "node_disk_read_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let write_time = statistics.first_with(hostname, "node_disk_write_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 let mut average_read_request_time_ms = (read_time * 1000.) / reads_completed;
                 average_read_request_time_ms = if average_read_request_time_ms.is_nan() { 0. } else { average_read_request_time_ms };
                 let mut average_write_request_time_ms = (write_time * 1000.) / writes_completed;
                 average_write_request_time_ms = if average_write_request_time_ms.is_nan() { 0. } else { average_write_request_time_ms };
-                let time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-                println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                         hostname,
-                         time.format("%H:%M:%S"),
-                         current_device,
-                         tps,
-                         read_bytes / (1024. * 1024.),
-                         write_bytes / (1024. * 1024.),
-                         (average_read_request_size + average_write_request_size) / (1024. * 1024.),
-                         queue_size,
-                         (average_read_request_time_ms + average_write_request_time_ms) / 2.
-                );
+                let time = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
+                let values = vec![
+                    tps,
+                    read_bytes / (1024. * 1024.),
+                    write_bytes / (1024. * 1024.),
+                    (average_read_request_size + average_write_request_size) / (1024. * 1024.),
+                    queue_size,
+                    (average_read_request_time_ms + average_write_request_time_ms) / 2.,
+                ];
+                rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: current_device.to_string(), labels: Vec::new(), values });
             }
         }
     }
+    rows
+}
+
+pub fn print_sar_d_row(
+    hostname: &str,
+    time: &str,
+    device: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+             hostname,
+             time,
+             device,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+    );
 }
 
+pub const SAR_D_FIELDS: &[&str] = &["tps", "rmb_per_s", "wmb_per_s", "areq_sz", "aqu_sz", "await"];
 pub fn print_sar_d_header()
 {
     println!("{:30} {:8} {:10} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
              "hostname",
-             "time",
+             time_header(),
              "DEV",
              "tps",
              "rMB/s",
@@ -161,80 +163,117 @@ pub fn print_sar_d_header()
     );
 }
 
-pub fn print_xfs_iops(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn xfs_iops_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_xfs_read_calls_total" && !row.first_value )
+        if statistics.rows(hostname, "node_xfs_read_calls_total").any(|(_, row)| !row.first_value)
         {
-            for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_xfs_read_calls_total").map(|((_, _, device, _), _)| device)
+            for current_device in statistics.rows(hostname, "node_xfs_read_calls_total").map(|((device, _), _)| device)
             {
-                let reads_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_xfs_read_calls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let writes_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_xfs_write_calls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_xfs_read_calls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-                println!("{:30} {:8} {:10} {:10.2} {:10.2}",
-                         hostname,
-                         time.format("%H:%M:%S"),
-                         current_device,
-                         writes_completed,
-                         reads_completed,
-                );
+                let reads_completed = statistics.first_with(hostname, "node_xfs_read_calls_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let writes_completed = statistics.first_with(hostname, "node_xfs_write_calls_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let time = statistics.first_with(hostname, "node_xfs_read_calls_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
+                let values = vec![
+                    writes_completed,
+                    reads_completed,
+                ];
+                rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: current_device.to_string(), labels: Vec::new(), values });
             }
         }
     }
+    rows
 }
 
+pub fn print_xfs_iops_row(
+    hostname: &str,
+    time: &str,
+    device: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10} {:10.2} {:10.2}",
+             hostname,
+             time,
+             device,
+             values[0],
+             values[1],
+    );
+}
+
+pub const XFS_IOPS_FIELDS: &[&str] = &["w_iops", "r_iops"];
 pub fn print_xfs_iops_header()
 {
     println!("{:30} {:8} {:10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "dev",
              "W_IOPS",
              "R_IOPS",
     );
 }
 
-pub fn print_iostat(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn iostat_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_disk_read_bytes_total" && !row.first_value )
+        if statistics.rows(hostname, "node_disk_read_bytes_total").any(|(_, row)| !row.first_value)
         {
-            for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_disk_read_bytes_total").map(|((_, _, device, _), _)| device)
+            for current_device in statistics.rows(hostname, "node_disk_read_bytes_total").map(|((device, _), _)| device)
             {
-                let reads_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let writes_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_writes_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let reads_completed = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let writes_completed = statistics.first_with(hostname, "node_disk_writes_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 let tps = reads_completed + writes_completed;
-                let read_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let write_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_written_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let read_total = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.delta_value).unwrap();
-                let write_total = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_written_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.delta_value).unwrap();
-                let time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-                println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                         hostname,
-                         time.format("%H:%M:%S"),
-                         current_device,
-                         tps,
-                         read_bytes / (1024. * 1024.),
-                         write_bytes / (1024. * 1024.),
-                         read_total / (1024. * 1024.),
-                         write_total / (1024. * 1024.),
-                );
+                let read_bytes = statistics.first_with(hostname, "node_disk_read_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let write_bytes = statistics.first_with(hostname, "node_disk_written_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let read_total = statistics.first_with(hostname, "node_disk_read_bytes_total", current_device).map(|statistic| statistic.delta_value).unwrap_or_default();
+                let write_total = statistics.first_with(hostname, "node_disk_written_bytes_total", current_device).map(|statistic| statistic.delta_value).unwrap_or_default();
+                let time = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
+                let values = vec![
+                    tps,
+                    read_bytes / (1024. * 1024.),
+                    write_bytes / (1024. * 1024.),
+                    read_total / (1024. * 1024.),
+                    write_total / (1024. * 1024.),
+                ];
+                rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: current_device.to_string(), labels: Vec::new(), values });
             }
         }
     }
+    rows
+}
+
+pub fn print_iostat_row(
+    hostname: &str,
+    time: &str,
+    device: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+             hostname,
+             time,
+             device,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+    );
 }
 
+pub const IOSTAT_FIELDS: &[&str] = &["tps", "mb_read_per_s", "mb_wrtn_per_s", "mb_read", "mb_wrtn"];
 pub fn print_iostat_header()
 {
     println!("{:30} {:8} {:10} {:>9} {:>9} {:>9} {:>9} {:>9}",
              "hostname",
-             "time",
+             time_header(),
              "Device",
              "tps",
              "MB_read/s",
@@ -244,28 +283,29 @@ pub fn print_iostat_header()
     );
 }
 
-pub fn print_iostat_x(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn iostat_x_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), row)| host == hostname && metric == "node_disk_read_bytes_total" && !row.first_value )
+        if statistics.rows(hostname, "node_disk_read_bytes_total").any(|(_, row)| !row.first_value)
         {
-            for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_disk_read_bytes_total").map(|((_, _, device, _), _)| device)
+            for current_device in statistics.rows(hostname, "node_disk_read_bytes_total").map(|((device, _), _)| device)
             {
-                let reads_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let writes_completed = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_writes_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let read_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let write_bytes = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_written_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let reads_completed = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let writes_completed = statistics.first_with(hostname, "node_disk_writes_completed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let read_bytes = statistics.first_with(hostname, "node_disk_read_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let write_bytes = statistics.first_with(hostname, "node_disk_written_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_disk_reads_merged does not exist for Mac.
-                let reads_merged = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_merged_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let reads_merged = statistics.first_with(hostname, "node_disk_reads_merged_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_disk_writes_merged does not exist for Mac.
-                let writes_merged = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_writes_merged_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-                let read_time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-                let write_time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_write_time_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+                let writes_merged = statistics.first_with(hostname, "node_disk_writes_merged_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let read_time = statistics.first_with(hostname, "node_disk_read_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let write_time = statistics.first_with(hostname, "node_disk_write_time_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // node_disk_io_time_weighted does not exist for Mac.
-                let queue = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_io_time_weighted_seconds_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
+                let queue = statistics.first_with(hostname, "node_disk_io_time_weighted_seconds_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 let mut read_percentage_merged = reads_merged / (reads_merged + reads_completed) * 100.;
                 read_percentage_merged = if read_percentage_merged.is_nan() { 0. } else { read_percentage_merged };
                 let mut write_percentage_merged = writes_merged / (writes_merged + writes_completed) * 100.;
@@ -278,36 +318,63 @@ pub fn print_iostat_x(
                 read_average_request_size = if read_average_request_size.is_nan() { 0. } else { read_average_request_size };
                 let mut write_average_request_size = write_bytes / writes_completed;
                 write_average_request_size = if write_average_request_size.is_nan() { 0. } else { write_average_request_size };
-                let time = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_reads_completed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+                let time = statistics.first_with(hostname, "node_disk_reads_completed_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
 
-                println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                         hostname,
-                         time.format("%H:%M:%S"),
-                         current_device,
-                         reads_completed,
-                         writes_completed,
-                         read_bytes / (1024.*1024.),
-                         write_bytes / (1024.*1024.),
-                         reads_merged,
-                         writes_merged,
-                         read_percentage_merged,
-                         write_percentage_merged,
-                         read_average_time_ms,
-                         write_average_time_ms,
-                         queue,
-                         read_average_request_size / (1024.*1024.),
-                         write_average_request_size / (1024.*1024.),
-                );
+                let values = vec![
+                    reads_completed,
+                    writes_completed,
+                    read_bytes / (1024.*1024.),
+                    write_bytes / (1024.*1024.),
+                    reads_merged,
+                    writes_merged,
+                    read_percentage_merged,
+                    write_percentage_merged,
+                    read_average_time_ms,
+                    write_average_time_ms,
+                    queue,
+                    read_average_request_size / (1024.*1024.),
+                    write_average_request_size / (1024.*1024.),
+                ];
+                rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: current_device.to_string(), labels: Vec::new(), values });
             }
         }
     }
+    rows
+}
+
+pub fn print_iostat_x_row(
+    hostname: &str,
+    time: &str,
+    device: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+             hostname,
+             time,
+             device,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+             values[8],
+             values[9],
+             values[10],
+             values[11],
+             values[12],
+    );
 }
 
+pub const IOSTAT_X_FIELDS: &[&str] = &["r_per_s", "w_per_s", "rmb_per_s", "wmb_per_s", "rrqm_per_s", "wrqm_per_s", "rrqm_percent", "wrqm_percent", "r_await", "w_await", "aqu_sz", "rareq_sz", "wareq_sz"];
 pub fn print_iostat_x_header()
 {
     println!("{:30} {:8} {:10} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
              "hostname",
-             "time",
+             time_header(),
              "Device",
              "r/s",
              "w/s",
@@ -1,13 +1,14 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT};
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NodeMemoryDetails {
     pub active_anon: f64,
     pub active: f64,
@@ -65,17 +66,12 @@ pub struct NodeMemoryDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
-    let Value::Gauge(value) = sample.value else { panic!("{} value enum type should be Gauge!", sample.metric) };
+    let Value::Gauge(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Gauge" }) };
     statistics
-        .entry((
-            hostname.to_string(),
-            sample.metric.clone(),
-            "".to_string(),
-            "".to_string(),
-        ))
+        .entry(hostname, &sample.metric, "", "")
         .and_modify(|row| {
             row.last_value = value;
             row.last_timestamp = sample.timestamp;
@@ -90,107 +86,174 @@ pub fn process_statistic(
                 first_value: true,
                 ..Default::default()
             });
+    Ok(())
 }
 
-pub fn print_sar_r(
+pub fn sar_r_rows(
     mode: &str,
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes")
+        if statistics.contains_metric(hostname, "node_memory_MemFree_bytes")
         {
-            let time = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+            let time = statistics.first(hostname, "node_memory_MemFree_bytes").map(|statistic| statistic.last_timestamp).unwrap_or_default();
 
-            let memory_free = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_available = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemAvailable_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
+            let memory_free = statistics.first(hostname, "node_memory_MemFree_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_available = statistics.first(hostname, "node_memory_MemAvailable_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_total = statistics.first(hostname, "node_memory_MemTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
             let memory_used = memory_total - memory_free;
-            let memory_buffers = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Buffers_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_cached = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Cached_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_commit = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Committed_AS_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_swap_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_active = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Active_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_inactive = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Inactive_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_dirty = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Dirty_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_anonymous = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_AnonPages_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_slab = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Slab_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_kernel_stack = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_KernelStack_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_pagetables = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_PageTables_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_swapcached = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapCached_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_shared = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Shmem_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_mapped = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Mapped_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_hardware_corrupted = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_HardwareCorrupted_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-            let memory_virtual_memory = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_VmallocTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let vmstat_oom_kill = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_oom_kill").map(|((_, _, _, _), statistic)| statistic.delta_value).unwrap();
-            match mode
+            let memory_buffers = statistics.first(hostname, "node_memory_Buffers_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_cached = statistics.first(hostname, "node_memory_Cached_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_commit = statistics.first(hostname, "node_memory_Committed_AS_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_swap_total = statistics.first(hostname, "node_memory_SwapTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_active = statistics.first(hostname, "node_memory_Active_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_inactive = statistics.first(hostname, "node_memory_Inactive_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_dirty = statistics.first(hostname, "node_memory_Dirty_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_anonymous = statistics.first(hostname, "node_memory_AnonPages_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_slab = statistics.first(hostname, "node_memory_Slab_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_kernel_stack = statistics.first(hostname, "node_memory_KernelStack_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_pagetables = statistics.first(hostname, "node_memory_PageTables_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_swapcached = statistics.first(hostname, "node_memory_SwapCached_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_shared = statistics.first(hostname, "node_memory_Shmem_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_mapped = statistics.first(hostname, "node_memory_Mapped_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_hardware_corrupted = statistics.first(hostname, "node_memory_HardwareCorrupted_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_virtual_memory = statistics.first(hostname, "node_memory_VmallocTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let vmstat_oom_kill = statistics.first(hostname, "node_vmstat_oom_kill").map(|statistic| statistic.delta_value).unwrap_or_default();
+            let values = match mode
             {
-                "normal" => {
-                    println!("{:30} {:8} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0}",
-                             hostname,
-                             time.format("%H:%M:%S"),
-                             memory_free / (1024. * 1024.),
-                             memory_available / (1024. * 1024.),
-                             memory_used / (1024. * 1024.),
-                             memory_used / memory_total * 100.,
-                             memory_buffers / (1024. * 1024.),
-                             memory_cached / (1024. * 1024.),
-                             memory_commit / (1024. * 1024.),
-                             memory_commit / (memory_total + memory_swap_total) * 100.,
-                             memory_active / (1024. * 1024.),
-                             memory_inactive / (1024. * 1024.),
-                             memory_dirty / (1024. * 1024.),
-                    );
-                },
-                "all" => {
-                    println!("{:30} {:8} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0}",
-                             hostname,
-                             time.format("%H:%M:%S"),
-                             memory_free / (1024. * 1024.),
-                             memory_available / (1024. * 1024.),
-                             memory_used / (1024. * 1024.),
-                             memory_used / memory_total * 100.,
-                             memory_buffers / (1024. * 1024.),
-                             memory_cached / (1024. * 1024.),
-                             memory_commit / (1024. * 1024.),
-                             memory_commit / (memory_total + memory_swap_total) * 100.,
-                             memory_active / (1024. * 1024.),
-                             memory_inactive / (1024. * 1024.),
-                             memory_dirty / (1024. * 1024.),
-                             memory_anonymous / (1024. * 1024.),
-                             memory_slab / (1024. * 1024.),
-                             memory_kernel_stack / (1024. * 1024.),
-                             memory_pagetables / (1024. * 1024.),
-                             memory_virtual_memory / (1024. * 1024.)
-                    );
-                },
-                "relevant" => {
-                    println!("{:30} {:8} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0}",
-                             hostname,
-                             time.format("%H:%M:%S"),
-                             memory_total / (1024. * 1024.),
-                             memory_swapcached / (1024. * 1024.),
-                             memory_kernel_stack / (1024. * 1024.),
-                             memory_hardware_corrupted / (1024.*1024.),
-                             memory_slab / (1024. * 1024.),
-                             memory_pagetables / (1024. * 1024.),
-                             memory_shared / (1024. * 1024.),
-                             memory_dirty / (1024. * 1024.),
-                             (memory_mapped - memory_shared).max(0.) / (1024. * 1024.),
-                             (memory_cached - memory_mapped.max(memory_shared) - memory_dirty) / (1024. * 1024.),
-                             memory_anonymous / (1024. * 1024.),
-                             memory_free / (1024. * 1024.),
-                             memory_available / (1024. * 1024.),
-                             vmstat_oom_kill,
-                    );
-                },
-                &_ => {},
-            }
+                "normal" => vec![
+                    memory_free / (1024. * 1024.),
+                    memory_available / (1024. * 1024.),
+                    memory_used / (1024. * 1024.),
+                    memory_used / memory_total * 100.,
+                    memory_buffers / (1024. * 1024.),
+                    memory_cached / (1024. * 1024.),
+                    memory_commit / (1024. * 1024.),
+                    memory_commit / (memory_total + memory_swap_total) * 100.,
+                    memory_active / (1024. * 1024.),
+                    memory_inactive / (1024. * 1024.),
+                    memory_dirty / (1024. * 1024.),
+                ],
+                "all" => vec![
+                    memory_free / (1024. * 1024.),
+                    memory_available / (1024. * 1024.),
+                    memory_used / (1024. * 1024.),
+                    memory_used / memory_total * 100.,
+                    memory_buffers / (1024. * 1024.),
+                    memory_cached / (1024. * 1024.),
+                    memory_commit / (1024. * 1024.),
+                    memory_commit / (memory_total + memory_swap_total) * 100.,
+                    memory_active / (1024. * 1024.),
+                    memory_inactive / (1024. * 1024.),
+                    memory_dirty / (1024. * 1024.),
+                    memory_anonymous / (1024. * 1024.),
+                    memory_slab / (1024. * 1024.),
+                    memory_kernel_stack / (1024. * 1024.),
+                    memory_pagetables / (1024. * 1024.),
+                    memory_virtual_memory / (1024. * 1024.),
+                ],
+                "relevant" => vec![
+                    memory_total / (1024. * 1024.),
+                    memory_swapcached / (1024. * 1024.),
+                    memory_kernel_stack / (1024. * 1024.),
+                    memory_hardware_corrupted / (1024.*1024.),
+                    memory_slab / (1024. * 1024.),
+                    memory_pagetables / (1024. * 1024.),
+                    memory_shared / (1024. * 1024.),
+                    memory_dirty / (1024. * 1024.),
+                    (memory_mapped - memory_shared).max(0.) / (1024. * 1024.),
+                    (memory_cached - memory_mapped.max(memory_shared) - memory_dirty) / (1024. * 1024.),
+                    memory_anonymous / (1024. * 1024.),
+                    memory_free / (1024. * 1024.),
+                    memory_available / (1024. * 1024.),
+                    vmstat_oom_kill,
+                ],
+                &_ => continue,
+            };
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
 }
 
+pub fn print_sar_r_row(
+    mode: &str,
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    match mode
+    {
+        "normal" => {
+            println!("{:30} {:8} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0}",
+                     hostname,
+                     time,
+                     values[0],
+                     values[1],
+                     values[2],
+                     values[3],
+                     values[4],
+                     values[5],
+                     values[6],
+                     values[7],
+                     values[8],
+                     values[9],
+                     values[10],
+            );
+        },
+        "all" => {
+            println!("{:30} {:8} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0} {:9.2} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0}",
+                     hostname,
+                     time,
+                     values[0],
+                     values[1],
+                     values[2],
+                     values[3],
+                     values[4],
+                     values[5],
+                     values[6],
+                     values[7],
+                     values[8],
+                     values[9],
+                     values[10],
+                     values[11],
+                     values[12],
+                     values[13],
+                     values[14],
+                     values[15],
+            );
+        },
+        "relevant" => {
+            println!("{:30} {:8} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0} {:9.0}",
+                     hostname,
+                     time,
+                     values[0],
+                     values[1],
+                     values[2],
+                     values[3],
+                     values[4],
+                     values[5],
+                     values[6],
+                     values[7],
+                     values[8],
+                     values[9],
+                     values[10],
+                     values[11],
+                     values[12],
+                     values[13],
+            );
+        },
+        &_ => {},
+    }
+}
+
+pub const SAR_R_FIELDS: &[&str] = &["mbmemfree", "mbavail", "mbmemused", "memused_percent", "mbbuffers", "mbcached", "mbcommit", "commit_percent", "mbactive", "mbinact", "mbdirty"];
+pub const SAR_R_ALL_FIELDS: &[&str] = &["mbmemfree", "mbavail", "mbmemused", "memused_percent", "mbbuffers", "mbcached", "mbcommit", "commit_percent", "mbactive", "mbinact", "mbdirty", "mbanonpg", "mbslab", "mbstack", "mbpgtbl", "mbvmused"];
+pub const MEM_RELEVANT_FIELDS: &[&str] = &["total", "swpcached", "kernelstk", "hwcorrupt", "slab", "pagetbls", "shared", "dirty", "mapped", "cached", "anon", "free", "avail", "oomkills"];
 pub fn print_sar_r_header(
     mode: &str,
 )
@@ -200,7 +263,7 @@ pub fn print_sar_r_header(
         "normal" => {
             println!("{:30} {:8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
                      "hostname",
-                     "time",
+                     time_header(),
                      "mbmemfree",
                      "mbavail",
                      "mbmemused",
@@ -217,7 +280,7 @@ pub fn print_sar_r_header(
         "all" => {
             println!("{:30} {:8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
                      "hostname",
-                     "time",
+                     time_header(),
                      "mbmemfree",
                      "mbavail",
                      "mbmemused",
@@ -239,7 +302,7 @@ pub fn print_sar_r_header(
         "relevant" => {
             println!("{:30} {:8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
                      "hostname",
-                     "time",
+                     time_header(),
                      "total",
                      "swpcached",
                      "kernelstk",
@@ -260,40 +323,59 @@ pub fn print_sar_r_header(
     }
 }
 
-pub fn print_sar_s(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_s_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapFree_bytes")
+        if statistics.contains_metric(hostname, "node_memory_SwapFree_bytes")
         {
-            let time = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+            let time = statistics.first(hostname, "node_memory_SwapFree_bytes").map(|statistic| statistic.last_timestamp).unwrap_or_default();
 
-            let swap_free = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let swap_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
+            let swap_free = statistics.first(hostname, "node_memory_SwapFree_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let swap_total = statistics.first(hostname, "node_memory_SwapTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
             let swap_used = swap_total - swap_free;
             let swap_used_percent = (swap_used / swap_total).max(0.) * 100.;
-            let swap_cached = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapCached_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
+            let swap_cached = statistics.first(hostname, "node_memory_SwapCached_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
             let swap_cached_percent = swap_cached / swap_used * 100.;
-            println!("{:30} {:8} {:10.0} {:10.0} {:10.2} {:10.0} {:10.2}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     swap_free / (1024. * 1024.),
-                     swap_used / (1024. * 1024.),
-                     swap_used_percent,
-                     swap_cached / (1024. * 1024.),
-                     if swap_cached_percent.is_nan() { 0. } else { swap_cached_percent },
-            );
+            let values = vec![
+                swap_free / (1024. * 1024.),
+                swap_used / (1024. * 1024.),
+                swap_used_percent,
+                swap_cached / (1024. * 1024.),
+                if swap_cached_percent.is_nan() { 0. } else { swap_cached_percent },
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_sar_s_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.0} {:10.0} {:10.2} {:10.0} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+    );
 }
 
+pub const SAR_S_FIELDS: &[&str] = &["mbswpfree", "mbswpused", "swpused_percent", "mbswpcad", "swpcad_percent"];
 pub fn print_sar_s_header()
 {
     println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "mbspwfree",
              "mbswpused",
              "%swpused",
@@ -329,7 +411,7 @@ pub fn create_memory_plots(
         let low_value: f64 = 0.0;
         let high_value = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _),_)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.memtotal + row.memtotal * 0.1) / (1024. * 1024.))
+            .map(|(_, row)| (row.memtotal + row.memtotal * 0.1) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let ((_, _), latest) = unlocked_historical_data.memory_details.iter()
@@ -355,12 +437,12 @@ pub fn create_memory_plots(
         // memory total
         let min_memory_total = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.memtotal / (1024. * 1024.))
+            .map(|(_, row)| row.memtotal / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_total = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.memtotal / (1024. * 1024.))
+            .map(|(_, row)| row.memtotal / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -374,12 +456,12 @@ pub fn create_memory_plots(
         // swap cached + kernelstack + hardware corrupted + slab + pagetables + dirty + cached + anonymous + memfree + (hugepages_total - hugepages_free * hugepagesize) 14
         let min_memory_hugepages_used = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| ((row.hugepages_total - row.hugepages_free) * row.hugepagesize) / (1024. * 1024.))
+            .map(|(_, row)| ((row.hugepages_total - row.hugepages_free) * row.hugepagesize) / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_hugepages_used = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)|  ((row.hugepages_total - row.hugepages_free) * row.hugepagesize) / (1024. * 1024.))
+            .map(|(_, row)|  ((row.hugepages_total - row.hugepages_free) * row.hugepagesize) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -393,12 +475,12 @@ pub fn create_memory_plots(
         // swap cached + kernelstack + hardware corrupted + slab + pagetables + dirty + cached + anonymous + memfree + (hugepages_free * hugepagesize) 13
         let min_memory_hugepages_used = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.hugepages_free * row.hugepagesize) / (1024. * 1024.))
+            .map(|(_, row)| (row.hugepages_free * row.hugepagesize) / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_hugepages_used = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)|  (row.hugepages_free * row.hugepagesize) / (1024. * 1024.))
+            .map(|(_, row)|  (row.hugepages_free * row.hugepagesize) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -412,12 +494,12 @@ pub fn create_memory_plots(
         // swap cached + kernelstack + hardware corrupted + slab + pagetables + dirty + cached + anonymous + memfree 12
         let min_memory_swapcached = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.swapcached / (1024. * 1024.))
+            .map(|(_, row)| row.swapcached / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_swapcached = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.swapcached / (1024. * 1024.))
+            .map(|(_, row)| row.swapcached / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -431,12 +513,12 @@ pub fn create_memory_plots(
         // kernelstack + hardware corrupted + slab + pagetables + dirty + cached + anonymous + memfree 11
         let min_memory_kernelstack = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.kernelstack / (1024. * 1024.))
+            .map(|(_, row)| row.kernelstack / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_kernelstack = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.kernelstack / (1024. * 1024.))
+            .map(|(_, row)| row.kernelstack / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -450,12 +532,12 @@ pub fn create_memory_plots(
         // hardware corrupted + slab + pagetables + dirty + cached + anonymous + memfree 10
         let min_memory_corrupted = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.hardwarecorrupted / (1024. * 1024.))
+            .map(|(_, row)| row.hardwarecorrupted / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_corrupted = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.hardwarecorrupted / (1024. * 1024.))
+            .map(|(_, row)| row.hardwarecorrupted / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -469,12 +551,12 @@ pub fn create_memory_plots(
         // slab + pagetables + dirty + cached + anonymous + memfree 9
         let min_memory_slab = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.slab / (1024. * 1024.))
+            .map(|(_, row)| row.slab / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_slab = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.slab / (1024. * 1024.))
+            .map(|(_, row)| row.slab / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -489,12 +571,12 @@ pub fn create_memory_plots(
         // pagetables + shmem + cached + anonymous + memfree 8
         let min_memory_pagetables = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.pagetables / (1024. * 1024.))
+            .map(|(_, row)| row.pagetables / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_pagetables = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.pagetables / (1024. * 1024.))
+            .map(|(_, row)| row.pagetables / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -509,12 +591,12 @@ pub fn create_memory_plots(
         // cached + anonymous + memfree 7
         let min_memory_dirty = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.dirty / (1024. * 1024.))
+            .map(|(_, row)| row.dirty / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_dirty = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.dirty / (1024. * 1024.))
+            .map(|(_, row)| row.dirty / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -529,12 +611,12 @@ pub fn create_memory_plots(
         // first looked like being inside cached, now it seems not to
         let min_memory_shared = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.shmem / (1024. * 1024.))
+            .map(|(_, row)| row.shmem / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_shared = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.shmem / (1024. * 1024.))
+            .map(|(_, row)| row.shmem / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -549,12 +631,12 @@ pub fn create_memory_plots(
         // cached - dirty + anonymous + memfree 5
         let min_memory_mapped = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.mapped - row.shmem).max(0.) / (1024. * 1024.))
+            .map(|(_, row)| (row.mapped - row.shmem).max(0.) / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_mapped = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.mapped - row.shmem).max(0.) / (1024. * 1024.))
+            .map(|(_, row)| (row.mapped - row.shmem).max(0.) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -570,12 +652,12 @@ pub fn create_memory_plots(
         // actual cached memory is without mapped and dirty memory.
         let min_memory_cached = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.cached - row.mapped.max(row.shmem) - row.dirty) / (1024. * 1024.))
+            .map(|(_, row)| (row.cached - row.mapped.max(row.shmem) - row.dirty) / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_cached = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.cached - row.mapped.max(row.shmem) - row.dirty) / (1024. * 1024.))
+            .map(|(_, row)| (row.cached - row.mapped.max(row.shmem) - row.dirty) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -590,12 +672,12 @@ pub fn create_memory_plots(
         // anonymous + memfree
         let min_memory_anonymous = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.anonpages / (1024. * 1024.))
+            .map(|(_, row)| row.anonpages / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_anonymous = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.anonpages / (1024. * 1024.))
+            .map(|(_, row)| row.anonpages / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -609,12 +691,12 @@ pub fn create_memory_plots(
         // memfree 2
         let min_memory_free = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.memfree / (1024. * 1024.))
+            .map(|(_, row)| row.memfree / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_free = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.memfree / (1024. * 1024.))
+            .map(|(_, row)| row.memfree / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -628,12 +710,12 @@ pub fn create_memory_plots(
         // available memory: line - RED
         let min_memory_available = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.memavailable / (1024. * 1024.))
+            .map(|(_, row)| row.memavailable / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_memory_available = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.memavailable / (1024. * 1024.))
+            .map(|(_, row)| row.memavailable / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.memory_details.iter()
@@ -656,7 +738,7 @@ pub fn create_memory_plots(
         let low_swap_value: f64 = 0.0;
         let high_swap_value = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _),_)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.swaptotal) / (1024. * 1024.))
+            .map(|(_, row)| (row.swaptotal) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
 
@@ -677,12 +759,12 @@ pub fn create_memory_plots(
         // swap total
         let min_swap_total = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.swaptotal / (1024. * 1024.))
+            .map(|(_, row)| row.swaptotal / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_swap_total = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.swaptotal / (1024. * 1024.))
+            .map(|(_, row)| row.swaptotal / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -695,12 +777,12 @@ pub fn create_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(1).filled()));
         let min_swap_used = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.swaptotal - row.swapfree) / (1024. * 1024.))
+            .map(|(_, row)| (row.swaptotal - row.swapfree) / (1024. * 1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_swap_used = unlocked_historical_data.memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.swaptotal - row.swapfree) / (1024. * 1024.))
+            .map(|(_, row)| (row.swaptotal - row.swapfree) / (1024. * 1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.memory_details.iter()
@@ -1,13 +1,15 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT};
+use crate::{Statistic, Statistics, Row, Labels, time_header, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT, StatisticError};
+use crate::node_cpu::CpuList;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NodeMiscDetails {
     pub some_cpu: f64,
     pub some_io: f64,
@@ -26,8 +28,8 @@ pub struct NodeMiscDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     match sample.metric.as_str()
     {
@@ -38,20 +40,11 @@ pub fn process_statistic(
         "node_pressure_memory_waiting_seconds_total" |
         "node_intr_total" |
         "node_context_switches_total" => {
-            let Value::Counter(value) = sample.value else { panic!("{} value enum type should be Counter!", sample.metric)};
+            let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
             statistics
-                .entry((
-                    hostname.to_string(),
-                    sample.metric.clone(),
-                    "".to_string(),
-                    "".to_string(),
-                ))
+                .entry(hostname, &sample.metric, "", "")
                 .and_modify( |row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
-                    row.first_value = false;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{}: {} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 } )
                 .or_insert(
@@ -64,19 +57,40 @@ pub fn process_statistic(
                     }
                 );
         },
+        "node_interrupts_total" => {
+            let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
+            let cpu = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "cpu" })?;
+            let interrupt_type = sample.labels.iter().find(|(label, _)| *label == "type").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "type" })?;
+            // the devices of the irq, such as nvme0q1 or eth0-TxRx-0, are kept with the irq, so the output shows what the irq is for.
+            let interrupt = match sample.labels.iter().find(|(label, _)| *label == "devices").map(|(_, value)| value).filter(|devices| !devices.is_empty())
+            {
+                Some(devices) => format!("{} {}", interrupt_type, devices.replace(' ', "")),
+                None => interrupt_type.to_string(),
+            };
+            statistics
+                .entry(hostname, &sample.metric, cpu, &interrupt)
+                .and_modify( |row| {
+                    row.update_counter(value, sample.timestamp);
+                    debug!("{}: {} cpu: {}, interrupt: {}, last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, cpu, interrupt, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
+                } )
+                .or_insert(
+                    Statistic
+                    {
+                        last_value: value,
+                        last_timestamp: sample.timestamp,
+                        first_value: true,
+                        ..Default::default()
+                    }
+                );
+        },
         "node_procs_running" |
         "node_procs_blocked" |
         "node_load1" |
         "node_load5" |
         "node_load15" => {
-            let Value::Gauge(value) = sample.value else { panic!("{} value enum type should be Gauge!", sample.metric)};
+            let Value::Gauge(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Gauge" }) };
             statistics
-                .entry((
-                    hostname.to_string(),
-                    sample.metric.clone(),
-                    "".to_string(),
-                    "".to_string(),
-                ))
+                .entry(hostname, &sample.metric, "", "")
                 .and_modify( |row| {
                     row.last_value = value;
                     row.last_timestamp = sample.timestamp;
@@ -95,42 +109,75 @@ pub fn process_statistic(
         },
         &_ => {},
     }
+    Ok(())
 }
 
-pub fn print_sar_q(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
+pub fn create_total(
+    metric: &str,
+    hostname: &str,
+    statistics: &mut Statistics,
 )
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    if metric == "node_interrupts_total"
     {
-        if statistics.iter().any(|((host, metric, _, _), _)| host == hostname && metric == "node_load1")
+        statistics.create_total(hostname, metric);
+    }
+}
+
+pub fn sar_q_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.contains_metric(hostname, "node_load1")
         {
-            let runqueue_size = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "runqueue_size_does_not_exist").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-            let tasklist_size = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "tasklist_size_does_not_exist").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-            let node_load_1 = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load1").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let node_load_5 = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load5").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let node_load_15 = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load15").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let node_procs_blocked = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_procs_blocked").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default();
-            let time = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_load1").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            println!("{:30} {:8} {:10.0} {:10.0} {:10.2} {:10.2} {:10.2} {:10.0}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     runqueue_size,
-                     tasklist_size,
-                     node_load_1,
-                     node_load_5,
-                     node_load_15,
-                     node_procs_blocked,
-            );
+            let runqueue_size = statistics.first(hostname, "runqueue_size_does_not_exist").map(|statistic| statistic.last_value).unwrap_or_default();
+            let tasklist_size = statistics.first(hostname, "tasklist_size_does_not_exist").map(|statistic| statistic.last_value).unwrap_or_default();
+            let node_load_1 = statistics.first(hostname, "node_load1").map(|statistic| statistic.last_value).unwrap_or_default();
+            let node_load_5 = statistics.first(hostname, "node_load5").map(|statistic| statistic.last_value).unwrap_or_default();
+            let node_load_15 = statistics.first(hostname, "node_load15").map(|statistic| statistic.last_value).unwrap_or_default();
+            let node_procs_blocked = statistics.first(hostname, "node_procs_blocked").map(|statistic| statistic.last_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_load1").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                runqueue_size,
+                tasklist_size,
+                node_load_1,
+                node_load_5,
+                node_load_15,
+                node_procs_blocked,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_sar_q_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.0} {:10.0} {:10.2} {:10.2} {:10.2} {:10.0}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+    );
 }
 
+pub const SAR_Q_FIELDS: &[&str] = &["runq_sz", "plist_sz", "ldavg_1", "ldavg_5", "ldavg_15", "blocked"];
 pub fn print_sar_q_header()
 {
     println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "runq-sz",
              "plist-sz",
              "ldavg-1",
@@ -140,39 +187,58 @@ pub fn print_sar_q_header()
     );
 }
 
-pub fn print_psi(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn psi_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_cpu_waiting_seconds_total")
+        if statistics.contains_metric(hostname, "node_pressure_cpu_waiting_seconds_total")
         {
-            let node_pressure_cpu_waiting_seconds_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_cpu_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let node_pressure_io_stalled_seconds_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_io_stalled_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let node_pressure_io_waiting_seconds_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_io_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let node_pressure_memory_stalled_seconds_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_memory_stalled_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let node_pressure_memory_waiting_seconds_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_memory_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+            let node_pressure_cpu_waiting_seconds_total = statistics.first(hostname, "node_pressure_cpu_waiting_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let node_pressure_io_stalled_seconds_total = statistics.first(hostname, "node_pressure_io_stalled_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let node_pressure_io_waiting_seconds_total = statistics.first(hostname, "node_pressure_io_waiting_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let node_pressure_memory_stalled_seconds_total = statistics.first(hostname, "node_pressure_memory_stalled_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let node_pressure_memory_waiting_seconds_total = statistics.first(hostname, "node_pressure_memory_waiting_seconds_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
 
-            let time = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_pressure_cpu_waiting_seconds_total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            println!("{:30} {:8} {:10.3} {:10.3} {:10.3} {:10.3} {:10.3}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     node_pressure_cpu_waiting_seconds_total,
-                     node_pressure_io_waiting_seconds_total,
-                     node_pressure_io_stalled_seconds_total,
-                     node_pressure_memory_waiting_seconds_total,
-                     node_pressure_memory_stalled_seconds_total,
-            );
+            let time = statistics.first(hostname, "node_pressure_cpu_waiting_seconds_total").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                node_pressure_cpu_waiting_seconds_total,
+                node_pressure_io_waiting_seconds_total,
+                node_pressure_io_stalled_seconds_total,
+                node_pressure_memory_waiting_seconds_total,
+                node_pressure_memory_stalled_seconds_total,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_psi_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.3} {:10.3} {:10.3} {:10.3} {:10.3}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+    );
 }
 
+pub const PSI_FIELDS: &[&str] = &["some_cpu", "some_io", "full_io", "some_mem", "full_mem"];
 pub fn print_psi_header()
 {
     println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "some cpu",
              "some io",
              "full io",
@@ -181,6 +247,128 @@ pub fn print_psi_header()
     );
 }
 
+// the numbered irqs in numeric order, followed by the named ones (NMI, LOC, ..), like /proc/interrupts.
+fn interrupt_order(
+    interrupt: &str,
+) -> (u64, String)
+{
+    let interrupt_type = interrupt.split(' ').next().unwrap_or_default();
+    (interrupt_type.parse::<u64>().unwrap_or(u64::MAX), interrupt.to_string())
+}
+
+// the label of a row is the irq, the cpu (with a cpu list) and the devices of the irq are extra labels.
+fn sar_i_row(
+    hostname: &str,
+    cpu: Option<u64>,
+    interrupt: &str,
+    statistic: &Statistic,
+) -> Row
+{
+    let (interrupt_type, devices) = interrupt.split_once(' ').unwrap_or((interrupt, ""));
+    let mut labels = Vec::new();
+    if let Some(cpu) = cpu
+    {
+        labels.push(("cpu".to_string(), cpu.to_string()));
+    }
+    if !devices.is_empty()
+    {
+        labels.push(("devices".to_string(), devices.to_string()));
+    }
+    Row { hostname: hostname.to_string(), timestamp: statistic.last_timestamp, label: interrupt_type.to_string(), labels, values: vec![statistic.per_second_value] }
+}
+
+// with a top n, sar-I only shows the n interrupts with the highest rate of every host.
+pub fn sar_i_rows(
+    cpus: Option<&CpuList>,
+    top: Option<usize>,
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if !statistics.rows(hostname, "node_interrupts_total").any(|(_, statistic)| !statistic.first_value)
+        {
+            continue;
+        }
+        let mut interrupts = statistics.rows(hostname, "node_interrupts_total")
+            .filter(|((cpu, _), _)| cpu == "total")
+            .map(|((_, interrupt), statistic)| (interrupt.as_str(), statistic))
+            .sorted_by_key(|(interrupt, _)| interrupt_order(interrupt))
+            .collect::<Vec<_>>();
+        // with a top n, the busiest interrupts come first.
+        if let Some(top) = top
+        {
+            interrupts.sort_by(|(_, a), (_, b)| b.per_second_value.total_cmp(&a.per_second_value));
+            interrupts.truncate(top);
+        }
+        let cpu_numbers = match cpus
+        {
+            Some(cpu_list) => statistics.rows(hostname, "node_interrupts_total")
+                .filter_map(|((cpu, _), _)| cpu.parse::<u64>().ok())
+                .unique()
+                .filter(|cpu| cpu_list.contains(*cpu))
+                .sorted()
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        for (interrupt, total) in interrupts
+        {
+            if cpus.is_none()
+            {
+                rows.push(sar_i_row(hostname, None, interrupt, total));
+                continue;
+            }
+            // with a cpu list, the interrupt is shown for every cpu.
+            for cpu in &cpu_numbers
+            {
+                if let Some(statistic) = statistics.get(hostname, "node_interrupts_total", &cpu.to_string(), interrupt)
+                {
+                    rows.push(sar_i_row(hostname, Some(*cpu), interrupt, statistic));
+                }
+            }
+        }
+    }
+    rows
+}
+
+pub fn print_sar_i_row(
+    cpus: Option<&CpuList>,
+    hostname: &str,
+    time: &str,
+    interrupt: &str,
+    labels: &Labels,
+    values: &[f64],
+)
+{
+    let label = |name: &str| labels.iter().find(|(label, _)| label == name).map(|(_, value)| value.as_str()).unwrap_or_default();
+    let cpu = if cpus.is_some() { format!(" {:3}", label("cpu")) } else { String::new() };
+    let line = format!("{:30} {:8}{} {:>6} {:10.2} {}",
+             hostname,
+             time,
+             cpu,
+             interrupt,
+             values[0],
+             label("devices"),
+    );
+    println!("{}", line.trim_end());
+}
+
+pub const SAR_I_FIELDS: &[&str] = &["intr_per_s"];
+pub fn print_sar_i_header(
+    cpus: Option<&CpuList>,
+)
+{
+    let cpu = if cpus.is_some() { format!(" {:3}", "CPU") } else { String::new() };
+    println!("{:30} {:8}{} {:>6} {:>10} devices",
+             "hostname",
+             time_header(),
+             cpu,
+             "INTR",
+             "intr/s",
+    );
+}
+
 pub fn create_misc_plots(
     historical_data: &Arc<Mutex<HistoricalData>>,
 )
@@ -204,27 +392,27 @@ pub fn create_misc_plots(
         let low_value: f64 = 0.0;
         let high_value_some_cpu = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.some_cpu)
+            .map(|(_, row)| row.some_cpu)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_some_io = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.some_io)
+            .map(|(_, row)| row.some_io)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_full_io = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.full_io)
+            .map(|(_, row)| row.full_io)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_some_mem = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.some_mem)
+            .map(|(_, row)| row.some_mem)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_full_mem = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.full_mem)
+            .map(|(_, row)| row.full_mem)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value = vec![high_value_some_cpu, high_value_some_io, high_value_full_io, high_value_some_mem, high_value_full_mem].into_iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
@@ -249,12 +437,12 @@ pub fn create_misc_plots(
 
         let min_some_cpu = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.some_cpu )
+            .map(|(_, row)| row.some_cpu )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_some_cpu = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.some_cpu )
+            .map(|(_, row)| row.some_cpu )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -267,12 +455,12 @@ pub fn create_misc_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(1).filled()));
         let min_some_io = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.some_io )
+            .map(|(_, row)| row.some_io )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_some_io = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.some_io )
+            .map(|(_, row)| row.some_io )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -285,12 +473,12 @@ pub fn create_misc_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(2).filled()));
         let min_full_io = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.full_io )
+            .map(|(_, row)| row.full_io )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_full_io = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.full_io )
+            .map(|(_, row)| row.full_io )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -303,12 +491,12 @@ pub fn create_misc_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(3).filled()));
         let min_some_mem = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.some_mem )
+            .map(|(_, row)| row.some_mem )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_some_mem = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.some_mem )
+            .map(|(_, row)| row.some_mem )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -321,12 +509,12 @@ pub fn create_misc_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(4).filled()));
         let min_full_mem = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.full_mem )
+            .map(|(_, row)| row.full_mem )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_full_mem = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.full_mem )
+            .map(|(_, row)| row.full_mem )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -361,17 +549,17 @@ pub fn create_misc_plots(
         let low_value: f64 = 0.0;
         let high_value_load_1 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.load_1)
+            .map(|(_, row)| row.load_1)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_load_5 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.load_5)
+            .map(|(_, row)| row.load_5)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_load_15 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.load_15)
+            .map(|(_, row)| row.load_15)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value = vec![high_value_load_1, high_value_load_5, high_value_load_15].into_iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
@@ -396,12 +584,12 @@ pub fn create_misc_plots(
 
         let min_load_1 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_1 )
+            .map(|(_, row)| row.load_1 )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_load_1 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_1 )
+            .map(|(_, row)| row.load_1 )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -415,12 +603,12 @@ pub fn create_misc_plots(
 
         let min_load_5 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_5 )
+            .map(|(_, row)| row.load_5 )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_load_5 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_5 )
+            .map(|(_, row)| row.load_5 )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -434,12 +622,12 @@ pub fn create_misc_plots(
 
         let min_load_15 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_15 )
+            .map(|(_, row)| row.load_15 )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_load_15 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_15 )
+            .map(|(_, row)| row.load_15 )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
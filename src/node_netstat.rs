@@ -0,0 +1,535 @@
+use std::sync::{Arc, Mutex};
+use prometheus_parse::{Value, Sample};
+use itertools::Itertools;
+use log::*;
+use plotters::prelude::*;
+use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
+
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT, StatisticError};
+
+/// The listen queue, syncookie and abort counters per second, for the tcp-listen plot.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeNetstatDetails {
+    pub listen_overflows: f64,
+    pub listen_drops: f64,
+    pub syncookies_sent: f64,
+    pub syncookies_recv: f64,
+    pub syncookies_failed: f64,
+    pub abort_on_data: f64,
+    pub abort_on_close: f64,
+    pub abort_on_memory: f64,
+    pub abort_on_timeout: f64,
+    pub abort_on_linger: f64,
+    pub abort_failed: f64,
+}
+
+
+pub fn process_statistic(
+    sample: &Sample,
+    hostname: &str,
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
+{
+    let value = match sample.value
+    {
+        // node_exporter exposes the netstat values as untyped, other exporters of the same values as counters or gauges.
+        // the netstat collector only exposes a subset of the fields by default (--collector.netstat.fields), the fields that are missing are shown as zero.
+        Value::Untyped(value) | Value::Counter(value) | Value::Gauge(value) => value,
+        _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped, Counter or Gauge" }),
+    };
+    statistics
+        .entry(hostname, &sample.metric, "", "")
+        .and_modify( |row| {
+            row.update_counter(value, sample.timestamp);
+            debug!("{}: {} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
+        } )
+        .or_insert(
+            Statistic
+            {
+                last_value: value,
+                last_timestamp: sample.timestamp,
+                first_value: true,
+                ..Default::default()
+            });
+    Ok(())
+}
+
+pub fn sar_n_tcp_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.rows(hostname, "node_netstat_Tcp_InSegs").any(|(_, statistic)| !statistic.first_value)
+        {
+            let active = statistics.first(hostname, "node_netstat_Tcp_ActiveOpens").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let passive = statistics.first(hostname, "node_netstat_Tcp_PassiveOpens").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let iseg = statistics.first(hostname, "node_netstat_Tcp_InSegs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let oseg = statistics.first(hostname, "node_netstat_Tcp_OutSegs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_netstat_Tcp_InSegs").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                active,
+                passive,
+                iseg,
+                oseg,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
+        }
+    }
+    rows
+}
+
+pub fn print_sar_n_tcp_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+    );
+}
+
+pub const SAR_N_TCP_FIELDS: &[&str] = &["active_per_s", "passive_per_s", "iseg_per_s", "oseg_per_s"];
+pub fn print_sar_n_tcp_header()
+{
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10}",
+             "hostname",
+             time_header(),
+             "active/s",
+             "passive/s",
+             "iseg/s",
+             "oseg/s",
+    );
+}
+
+pub fn sar_n_etcp_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.rows(hostname, "node_netstat_Tcp_RetransSegs").any(|(_, statistic)| !statistic.first_value)
+        {
+            let atmptf = statistics.first(hostname, "node_netstat_Tcp_AttemptFails").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let estres = statistics.first(hostname, "node_netstat_Tcp_EstabResets").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let retrans = statistics.first(hostname, "node_netstat_Tcp_RetransSegs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let isegerr = statistics.first(hostname, "node_netstat_Tcp_InErrs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let orsts = statistics.first(hostname, "node_netstat_Tcp_OutRsts").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let output_segments = statistics.first(hostname, "node_netstat_Tcp_OutSegs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            // the retransmitted segments as a percentage of the segments sent.
+            let retrans_percent = if output_segments > 0. { retrans / output_segments * 100. } else { 0. };
+            let time = statistics.first(hostname, "node_netstat_Tcp_RetransSegs").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                atmptf,
+                estres,
+                retrans,
+                isegerr,
+                orsts,
+                retrans_percent,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
+        }
+    }
+    rows
+}
+
+pub fn print_sar_n_etcp_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+    );
+}
+
+pub const SAR_N_ETCP_FIELDS: &[&str] = &["atmptf_per_s", "estres_per_s", "retrans_per_s", "isegerr_per_s", "orsts_per_s", "retrans_percent"];
+pub fn print_sar_n_etcp_header()
+{
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+             "hostname",
+             time_header(),
+             "atmptf/s",
+             "estres/s",
+             "retrans/s",
+             "isegerr/s",
+             "orsts/s",
+             "%retrans",
+    );
+}
+
+pub fn sar_n_udp_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.rows(hostname, "node_netstat_Udp_InDatagrams").any(|(_, statistic)| !statistic.first_value)
+        {
+            let idgm = statistics.first(hostname, "node_netstat_Udp_InDatagrams").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let odgm = statistics.first(hostname, "node_netstat_Udp_OutDatagrams").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let noport = statistics.first(hostname, "node_netstat_Udp_NoPorts").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let idgmerr = statistics.first(hostname, "node_netstat_Udp_InErrors").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_netstat_Udp_InDatagrams").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                idgm,
+                odgm,
+                noport,
+                idgmerr,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
+        }
+    }
+    rows
+}
+
+pub fn print_sar_n_udp_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+    );
+}
+
+pub const SAR_N_UDP_FIELDS: &[&str] = &["idgm_per_s", "odgm_per_s", "noport_per_s", "idgmerr_per_s"];
+pub fn print_sar_n_udp_header()
+{
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10}",
+             "hostname",
+             time_header(),
+             "idgm/s",
+             "odgm/s",
+             "noport/s",
+             "idgmerr/s",
+    );
+}
+
+pub fn sar_n_ip_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.rows(hostname, "node_netstat_Ip_InReceives").any(|(_, statistic)| !statistic.first_value)
+        {
+            let irec = statistics.first(hostname, "node_netstat_Ip_InReceives").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let fwddgm = statistics.first(hostname, "node_netstat_Ip_ForwDatagrams").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let idel = statistics.first(hostname, "node_netstat_Ip_InDelivers").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let orq = statistics.first(hostname, "node_netstat_Ip_OutRequests").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let asmrq = statistics.first(hostname, "node_netstat_Ip_ReasmReqds").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let asmok = statistics.first(hostname, "node_netstat_Ip_ReasmOKs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let fragok = statistics.first(hostname, "node_netstat_Ip_FragOKs").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let fragcrt = statistics.first(hostname, "node_netstat_Ip_FragCreates").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_netstat_Ip_InReceives").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                irec,
+                fwddgm,
+                idel,
+                orq,
+                asmrq,
+                asmok,
+                fragok,
+                fragcrt,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
+        }
+    }
+    rows
+}
+
+pub fn print_sar_n_ip_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+    );
+}
+
+pub const SAR_N_IP_FIELDS: &[&str] = &["irec_per_s", "fwddgm_per_s", "idel_per_s", "orq_per_s", "asmrq_per_s", "asmok_per_s", "fragok_per_s", "fragcrt_per_s"];
+pub fn print_sar_n_ip_header()
+{
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+             "hostname",
+             time_header(),
+             "irec/s",
+             "fwddgm/s",
+             "idel/s",
+             "orq/s",
+             "asmrq/s",
+             "asmok/s",
+             "fragok/s",
+             "fragcrt/s",
+    );
+}
+
+pub fn sar_n_eip_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.rows(hostname, "node_netstat_Ip_InHdrErrors").any(|(_, statistic)| !statistic.first_value)
+        {
+            let ihdrerr = statistics.first(hostname, "node_netstat_Ip_InHdrErrors").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let iadrerr = statistics.first(hostname, "node_netstat_Ip_InAddrErrors").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let iukwnpr = statistics.first(hostname, "node_netstat_Ip_InUnknownProtos").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let idisc = statistics.first(hostname, "node_netstat_Ip_InDiscards").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let odisc = statistics.first(hostname, "node_netstat_Ip_OutDiscards").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let onort = statistics.first(hostname, "node_netstat_Ip_OutNoRoutes").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let asmf = statistics.first(hostname, "node_netstat_Ip_ReasmFails").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let fragf = statistics.first(hostname, "node_netstat_Ip_FragFails").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_netstat_Ip_InHdrErrors").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                ihdrerr,
+                iadrerr,
+                iukwnpr,
+                idisc,
+                odisc,
+                onort,
+                asmf,
+                fragf,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
+        }
+    }
+    rows
+}
+
+pub fn print_sar_n_eip_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+    );
+}
+
+pub const SAR_N_EIP_FIELDS: &[&str] = &["ihdrerr_per_s", "iadrerr_per_s", "iukwnpr_per_s", "idisc_per_s", "odisc_per_s", "onort_per_s", "asmf_per_s", "fragf_per_s"];
+pub fn print_sar_n_eip_header()
+{
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+             "hostname",
+             time_header(),
+             "ihdrerr/s",
+             "iadrerr/s",
+             "iukwnpr/s",
+             "idisc/s",
+             "odisc/s",
+             "onort/s",
+             "asmf/s",
+             "fragf/s",
+    );
+}
+
+pub fn tcp_listen_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
+{
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
+    {
+        if statistics.rows(hostname, "node_netstat_TcpExt_ListenOverflows").any(|(_, statistic)| !statistic.first_value)
+        {
+            let values = vec![
+                statistics.first(hostname, "node_netstat_TcpExt_ListenOverflows").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_ListenDrops").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_SyncookiesSent").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_SyncookiesRecv").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_SyncookiesFailed").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnData").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnClose").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnMemory").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnTimeout").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_TCPAbortOnLinger").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+                statistics.first(hostname, "node_netstat_TcpExt_TCPAbortFailed").map(|statistic| statistic.per_second_value).unwrap_or_default(),
+            ];
+            let time = statistics.first(hostname, "node_netstat_TcpExt_ListenOverflows").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
+        }
+    }
+    rows
+}
+
+pub fn print_tcp_listen_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+             values[8],
+             values[9],
+             values[10],
+    );
+}
+
+pub const TCP_LISTEN_FIELDS: &[&str] = &["listen_overflows_per_s", "listen_drops_per_s", "syncookies_sent_per_s", "syncookies_recv_per_s", "syncookies_failed_per_s", "abort_on_data_per_s", "abort_on_close_per_s", "abort_on_memory_per_s", "abort_on_timeout_per_s", "abort_on_linger_per_s", "abort_failed_per_s"];
+pub fn print_tcp_listen_header()
+{
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+             "hostname",
+             time_header(),
+             "lovfl/s",
+             "ldrop/s",
+             "scksent/s",
+             "sckrecv/s",
+             "sckfail/s",
+             "abdata/s",
+             "abclose/s",
+             "abmem/s",
+             "abtmo/s",
+             "ablngr/s",
+             "abfail/s",
+    );
+}
+
+// a field of the netstat details, to draw the series of the plot in a loop.
+type NetstatValue = fn(&NodeNetstatDetails) -> f64;
+
+pub fn create_netstat_plots(
+    historical_data: &Arc<Mutex<HistoricalData>>,
+)
+{
+    let unlocked_historical_data = historical_data.lock().unwrap();
+    for filter_hostname in unlocked_historical_data.netstat_details.keys().map(|(hostname, _)| hostname).unique()
+    {
+        let start_time = unlocked_historical_data.netstat_details
+            .keys()
+            .filter(|(hostname, _)| hostname == filter_hostname)
+            .map(|(_, timestamp)| timestamp)
+            .min()
+            .unwrap();
+        let end_time = unlocked_historical_data.netstat_details
+            .keys()
+            .filter(|(hostname, _)| hostname == filter_hostname)
+            .map(|(_, timestamp)| timestamp)
+            .max()
+            .unwrap();
+        let series: [(&str, NetstatValue); 11] = [
+            ("listen overflows", |row| row.listen_overflows),
+            ("listen drops", |row| row.listen_drops),
+            ("syncookies sent", |row| row.syncookies_sent),
+            ("syncookies received", |row| row.syncookies_recv),
+            ("syncookies failed", |row| row.syncookies_failed),
+            ("abort on data", |row| row.abort_on_data),
+            ("abort on close", |row| row.abort_on_close),
+            ("abort on memory", |row| row.abort_on_memory),
+            ("abort on timeout", |row| row.abort_on_timeout),
+            ("abort on linger", |row| row.abort_on_linger),
+            ("abort failed", |row| row.abort_failed),
+        ];
+        let low_value: f64 = 0.0;
+        // a little headroom, so that a host without any overflows still has a y axis.
+        let high_value = unlocked_historical_data.netstat_details.iter()
+            .filter(|((hostname, _), _)| hostname == filter_hostname)
+            .flat_map(|(_, row)| series.iter().map(move |(_, value)| value(row)))
+            .fold(1.0_f64, f64::max) * 1.1;
+        let filename = format!("{filter_hostname}_tcp_listen.png");
+
+        let root = BitMapBackend::new(&filename, (1280,900)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let mut contextarea = ChartBuilder::on(&root)
+            .set_label_area_size(LabelAreaPosition::Left, LABEL_AREA_SIZE_LEFT)
+            .set_label_area_size(LabelAreaPosition::Bottom, LABEL_AREA_SIZE_BOTTOM)
+            .set_label_area_size(LabelAreaPosition::Right, LABEL_AREA_SIZE_RIGHT)
+            .caption(format!("TCP listen queue, syncookies and aborts: {}",filter_hostname), (CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE))
+            .build_cartesian_2d(*start_time..*end_time, low_value..high_value)
+            .unwrap();
+        contextarea.configure_mesh()
+            .x_labels(4)
+            .x_label_formatter(&|x| x.to_rfc3339())
+            .y_desc("Events per second")
+            .label_style((MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE))
+            .draw()
+            .unwrap();
+        for (index, (description, value)) in series.iter().enumerate()
+        {
+            let min_value = unlocked_historical_data.netstat_details.iter()
+                .filter(|((hostname, _), _)| hostname == filter_hostname)
+                .map(|(_, row)| value(row))
+                .min_by(|a, b| a.partial_cmp(b).unwrap())
+                .unwrap();
+            let max_value = unlocked_historical_data.netstat_details.iter()
+                .filter(|((hostname, _), _)| hostname == filter_hostname)
+                .map(|(_, row)| value(row))
+                .max_by(|a, b| a.partial_cmp(b).unwrap())
+                .unwrap();
+            contextarea.draw_series(LineSeries::new(unlocked_historical_data.netstat_details.iter()
+                                                        .filter(|((hostname, _), _)| hostname == filter_hostname)
+                                                        .map(|((_, timestamp), row)| (*timestamp, value(row))),
+                                                    Palette99::pick(index + 1))
+            )
+                .unwrap()
+                .label(format!("{:25} min: {:10.2}, max: {:10.2}", description, min_value, max_value))
+                .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(index + 1).filled()));
+        }
+        contextarea.configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.7))
+            .label_font((LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE))
+            .position(UpperLeft)
+            .draw()
+            .unwrap();
+    }
+}
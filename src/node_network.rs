@@ -1,13 +1,14 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE};
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NodeNetworkDetails {
     pub receive_packets: f64,
     pub transmit_packets: f64,
@@ -41,8 +42,8 @@ pub struct NodeNetworkDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     match sample.metric.as_str()
     {
@@ -61,17 +62,13 @@ pub fn process_statistic(
         "node_network_transmit_carrier_total" |
         "node_network_receive_fifo_total" |
         "node_network_transmit_fifo_total" => {
-            let Value::Counter(value) = sample.value else { panic!("{} value enum type should be Counter!", sample.metric)};
-            let device = sample.labels.iter().find(|(label, _)| *label == "device").map(|(_, value)| value).unwrap();
-            if device.eq("lo") { return };
+            let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
+            let device = sample.labels.iter().find(|(label, _)| *label == "device").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "device" })?;
+            if device.eq("lo") { return Ok(()) };
             statistics
-                .entry(( hostname.to_string(), sample.metric.clone(), device.to_string(), "".to_string() ))
+                .entry(hostname, &sample.metric, device, "")
                 .and_modify( |row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
-                    row.first_value = false;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{} device: {}, last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", sample.metric, device, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 } )
                 .or_insert(
@@ -94,14 +91,9 @@ pub fn process_statistic(
         "node_sockstat_UDP6_inuse" |
         "node_sockstat_RAW6_inuse" |
         "node_sockstat_FRAG6_inuse" => {
-            let Value::Gauge(value) = sample.value else { panic!("{} value enum type should be Gauge!", sample.metric)};
+            let Value::Gauge(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Gauge" }) };
             statistics
-                .entry((
-                    hostname.to_string(),
-                    sample.metric.clone(),
-                    "".to_string(),
-                    "".to_string(),
-                ))
+                .entry(hostname, &sample.metric, "", "")
                 .and_modify( |row| {
                     row.last_value = value;
                     row.last_timestamp = sample.timestamp;
@@ -120,16 +112,12 @@ pub fn process_statistic(
         "node_softnet_dropped_total" |
         "node_softnet_processed_total" |
         "node_softnet_times_squeezed_total" => {
-            let Value::Counter(value) = sample.value else { panic!("{} value enum type should be Counter!", sample.metric)};
-            let cpu = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).unwrap();
+            let Value::Counter(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Counter" }) };
+            let cpu = sample.labels.iter().find(|(label, _)| *label == "cpu").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "cpu" })?;
             statistics
-                .entry(( hostname.to_string(), sample.metric.clone(), cpu.to_string(), "".to_string() ))
+                .entry(hostname, &sample.metric, cpu, "")
                 .and_modify( |row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
-                    row.first_value = false;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{}: metric: {} cpu: {}, last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, cpu, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 } )
                 .or_insert(
@@ -144,15 +132,16 @@ pub fn process_statistic(
         }
         &_ => {},
     }
+    Ok(())
 }
 
 pub fn create_total(
-    sample: &Sample,
-    host: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
+    metric: &str,
+    hostname: &str,
+    statistics: &mut Statistics,
 )
 {
-    match sample.metric.as_str()
+    match metric
     {
         "node_network_receive_packets_total" |
         "node_network_transmit_packets_total" |
@@ -168,78 +157,65 @@ pub fn create_total(
         "node_network_transmit_drop_total" |
         "node_network_transmit_carrier_total" |
         "node_network_receive_fifo_total" |
-        "node_network_transmit_fifo_total" => {
-            let last_timestamp = statistics.iter().find(|((hostname, metric, device, _), _)| hostname == host && metric == &sample.metric && device != "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            let per_second_value = statistics.iter().filter(|((hostname, metric, device, _), _)| hostname == host && metric == &sample.metric && device != "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let first_val = statistics.iter().find(|((hostname, metric, device, _), _)| hostname == host && metric == &sample.metric && device != "total").map(|((_, _, _, _), statistic)| statistic.first_value).unwrap();
-            statistics.entry((host.to_string(), sample.metric.to_string(), "total".to_string(), "".to_string()))
-                .and_modify(|row| {
-                    row.per_second_value = per_second_value;
-                    row.last_timestamp = last_timestamp;
-                    row.first_value = first_val;
-                })
-                .or_insert(Statistic {
-                    per_second_value,
-                    last_timestamp,
-                    first_value: first_val,
-                    ..Default::default()
-                });
-        },
+        "node_network_transmit_fifo_total" |
         "node_softnet_dropped_total" |
         "node_softnet_processed_total" |
-        "node_softnet_times_squeezed_total" => {
-            let last_timestamp = statistics.iter().find(|((hostname, metric, cpu, _), _)| hostname == host && metric == &sample.metric && cpu != "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            let per_second_value = statistics.iter().filter(|((hostname, metric, cpu, _), _)| hostname == host && metric == &sample.metric && cpu != "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let first_val = statistics.iter().find(|((hostname, metric, cpu, _), _)| hostname == host && metric == &sample.metric && cpu != "total").map(|((_, _, _, _), statistic)| statistic.first_value).unwrap();
-            statistics.entry((host.to_string(), sample.metric.to_string(), "total".to_string(), "".to_string()))
-                .and_modify(|row| {
-                    row.per_second_value = per_second_value;
-                    row.last_timestamp = last_timestamp;
-                    row.first_value = first_val;
-                })
-                .or_insert(Statistic {
-                    per_second_value,
-                    last_timestamp,
-                    first_value: first_val,
-                    ..Default::default()
-                });
-        }
+        "node_softnet_times_squeezed_total" => statistics.create_total(hostname, metric),
         &_ => {},
     }
 }
 
-pub fn print_sar_n_soft(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_n_soft_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && !statistic.first_value && metric == "node_softnet_processed_total").count() > 0
+        if statistics.rows(hostname, "node_softnet_processed_total").any(|(_, statistic)| !statistic.first_value)
         {
-            let soft_total = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_softnet_processed_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let soft_dropped = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_softnet_dropped_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let soft_squeezed = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_softnet_times_squeezed_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let soft_interproc_intr= statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_softnet_interpoc_intr_doesnotexist" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let soft_flow_limit = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_softnet_flow_limit_doesnotexist" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let time = statistics.iter().find(|((host, metric, cpu, _), _)| host == hostname && metric == "node_softnet_processed_total" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     soft_total,
-                     soft_dropped,
-                     soft_squeezed,
-                     soft_interproc_intr,
-                     soft_flow_limit,
-            );
+            let soft_total = statistics.first_with(hostname, "node_softnet_processed_total", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let soft_dropped = statistics.first_with(hostname, "node_softnet_dropped_total", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let soft_squeezed = statistics.first_with(hostname, "node_softnet_times_squeezed_total", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let soft_interproc_intr= statistics.first_with(hostname, "node_softnet_interpoc_intr_doesnotexist", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let soft_flow_limit = statistics.first_with(hostname, "node_softnet_flow_limit_doesnotexist", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let time = statistics.first_with(hostname, "node_softnet_processed_total", "total").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                soft_total,
+                soft_dropped,
+                soft_squeezed,
+                soft_interproc_intr,
+                soft_flow_limit,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
 }
 
+pub fn print_sar_n_soft_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+    );
+}
+
+pub const SAR_N_SOFT_FIELDS: &[&str] = &["total_per_s", "dropd_per_s", "squeezd_per_s", "rx_rps_per_s", "flw_lim_per_s"];
 pub fn print_sar_n_soft_header()
 {
     println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "total/s",
              "dropd/s",
              "squeezd/s",
@@ -248,50 +224,73 @@ pub fn print_sar_n_soft_header()
     );
 }
 
-pub fn print_sar_n_edev(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_n_edev_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_network_receive_errs_total").count() > 0
+        if statistics.contains_metric(hostname, "node_network_receive_errs_total")
         {
-            for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_network_receive_errs_total").map(|((_, _, device, _), _)| device)
+            for current_device in statistics.rows(hostname, "node_network_receive_errs_total").map(|((device, _), _)| device)
             {
-                let time = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_errs_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap();
+                let time = statistics.first_with(hostname, "node_network_receive_errs_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
 
-                let receive_errors = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_errs_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let transmit_errors = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_errs_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let transmit_collisions = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_colls_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let receive_drop = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_drop_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
+                let receive_errors = statistics.first_with(hostname, "node_network_receive_errs_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let transmit_errors = statistics.first_with(hostname, "node_network_transmit_errs_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let transmit_collisions = statistics.first_with(hostname, "node_network_transmit_colls_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let receive_drop = statistics.first_with(hostname, "node_network_receive_drop_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
                 // Mac OSX does not have transmit_drop, transmit_carrier and fifo statistics.
-                let transmit_drop = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_drop_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap_or_default();
-                let transmit_carrier = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_carrier_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap_or_default();
-                let receive_fifo = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_fifo_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap_or_default();
-                let transmit_fifo = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_fifo_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap_or_default();
-                println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                         hostname,
-                         time.format("%H:%M:%S"),
-                         current_device,
-                         receive_errors,
-                         transmit_errors,
-                         transmit_collisions,
-                         receive_drop,
-                         transmit_drop,
-                         transmit_carrier,
-                         receive_fifo,
-                         transmit_fifo,
-                );
+                let transmit_drop = statistics.first_with(hostname, "node_network_transmit_drop_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let transmit_carrier = statistics.first_with(hostname, "node_network_transmit_carrier_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let receive_fifo = statistics.first_with(hostname, "node_network_receive_fifo_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let transmit_fifo = statistics.first_with(hostname, "node_network_transmit_fifo_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let values = vec![
+                    receive_errors,
+                    transmit_errors,
+                    transmit_collisions,
+                    receive_drop,
+                    transmit_drop,
+                    transmit_carrier,
+                    receive_fifo,
+                    transmit_fifo,
+                ];
+                rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: current_device.to_string(), labels: Vec::new(), values });
             }
         }
     }
+    rows
 }
 
+pub fn print_sar_n_edev_row(
+    hostname: &str,
+    time: &str,
+    device: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+             hostname,
+             time,
+             device,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+    );
+}
+
+pub const SAR_N_EDEV_FIELDS: &[&str] = &["rxerr_per_s", "txerr_per_s", "coll_per_s", "rxdrop_per_s", "txdrop_per_s", "txcarr_per_s", "rxfifo_per_s", "txfifo_per_s"];
 pub fn print_sar_n_edev_header()
 {
     println!("{:30} {:8} {:10} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
              "hostname",
-             "time",
+             time_header(),
              "IFACE",
              "rxerr/s",
              "txerr/s",
@@ -304,46 +303,68 @@ pub fn print_sar_n_edev_header()
     );
 }
 
-pub fn print_sar_n_dev(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_n_dev_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && !statistic.first_value && metric == "node_network_receive_packets_total").count() > 0
+        if statistics.rows(hostname, "node_network_receive_packets_total").any(|(_, statistic)| !statistic.first_value)
         {
-            for current_device in statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_network_receive_packets_total").map(|((_, _, device, _), _)| device)
+            for current_device in statistics.rows(hostname, "node_network_receive_packets_total").map(|((device, _), _)| device)
             {
-                let receive_packets = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_packets_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let transmit_packets = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_packets_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let receive_bytes = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let transmit_bytes = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_bytes_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let compressed_packets_received = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_compressed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap_or_default();
-                let compressed_packets_transmit = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_transmit_compressed_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap_or_default();
-                let multicast_packets_received = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_multicast_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-                let time = statistics.iter().filter(|((host, metric, device, _), _)| host == hostname && metric == "node_network_receive_packets_total" && device == current_device).map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap();
-                println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
-                         hostname,
-                         time.format("%H:%M:%S"),
-                         current_device,
-                         receive_packets,
-                         transmit_packets,
-                         receive_bytes / (1024. * 1024.),
-                         transmit_bytes / (1024. * 1024.),
-                         compressed_packets_received,
-                         compressed_packets_transmit,
-                         multicast_packets_received,
-                );
+                let receive_packets = statistics.first_with(hostname, "node_network_receive_packets_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let transmit_packets = statistics.first_with(hostname, "node_network_transmit_packets_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let receive_bytes = statistics.first_with(hostname, "node_network_receive_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let transmit_bytes = statistics.first_with(hostname, "node_network_transmit_bytes_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let compressed_packets_received = statistics.first_with(hostname, "node_network_receive_compressed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let compressed_packets_transmit = statistics.first_with(hostname, "node_network_transmit_compressed_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let multicast_packets_received = statistics.first_with(hostname, "node_network_receive_multicast_total", current_device).map(|statistic| statistic.per_second_value).unwrap_or_default();
+                let time = statistics.first_with(hostname, "node_network_receive_packets_total", current_device).map(|statistic| statistic.last_timestamp).unwrap_or_default();
+                let values = vec![
+                    receive_packets,
+                    transmit_packets,
+                    receive_bytes / (1024. * 1024.),
+                    transmit_bytes / (1024. * 1024.),
+                    compressed_packets_received,
+                    compressed_packets_transmit,
+                    multicast_packets_received,
+                ];
+                rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: current_device.to_string(), labels: Vec::new(), values });
             }
         }
     }
+    rows
 }
 
+pub fn print_sar_n_dev_row(
+    hostname: &str,
+    time: &str,
+    device: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2} {:9.2}",
+             hostname,
+             time,
+             device,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+    );
+}
+
+pub const SAR_N_DEV_FIELDS: &[&str] = &["rxpck_per_s", "txpck_per_s", "rxmb_per_s", "txmb_per_s", "rxcmp_per_s", "txcmp_per_s", "rxmcst_per_s"];
 pub fn print_sar_n_dev_header()
 {
     println!("{:30} {:8} {:10} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
              "hostname",
-             "time",
+             time_header(),
              "IFACE",
              "rxpck/s",
              "txpck/s",
@@ -355,40 +376,60 @@ pub fn print_sar_n_dev_header()
     );
 }
 
-pub fn print_sar_n_sock(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_n_sock_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_sockets_used").count() > 0
+        if statistics.contains_metric(hostname, "node_sockstat_sockets_used")
         {
-            let sockets_total = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_sockets_used").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets_tcp = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_TCP_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets_udp = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_UDP_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets_raw = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_RAW_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets_frag = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_FRAG_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets_timedwait = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_TCP_tw").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let time = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_sockets_used").map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap_or_default();
-            println!("{:30} {:8} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     sockets_total,
-                     sockets_tcp,
-                     sockets_udp,
-                     sockets_raw,
-                     sockets_frag,
-                     sockets_timedwait,
-            );
+            let sockets_total = statistics.first(hostname, "node_sockstat_sockets_used").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets_tcp = statistics.first(hostname, "node_sockstat_TCP_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets_udp = statistics.first(hostname, "node_sockstat_UDP_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets_raw = statistics.first(hostname, "node_sockstat_RAW_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets_frag = statistics.first(hostname, "node_sockstat_FRAG_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets_timedwait = statistics.first(hostname, "node_sockstat_TCP_tw").map(|statistic| statistic.last_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_sockstat_sockets_used").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                sockets_total,
+                sockets_tcp,
+                sockets_udp,
+                sockets_raw,
+                sockets_frag,
+                sockets_timedwait,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
 }
 
+pub fn print_sar_n_sock_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+    );
+}
+
+pub const SAR_N_SOCK_FIELDS: &[&str] = &["totsck", "tcpsck", "udpsck", "rawsck", "ip_frag", "tcp_tw"];
 pub fn print_sar_n_sock_header()
 {
     println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "totsck",
              "tcpsck",
              "udpsck",
@@ -398,36 +439,54 @@ pub fn print_sar_n_sock_header()
     );
 }
 
-pub fn print_sar_n_sock6(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_n_sock6_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_TCP6_inuse").count() > 0
+        if statistics.contains_metric(hostname, "node_sockstat_TCP6_inuse")
         {
-            let sockets6_tcp = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_TCP6_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets6_udp= statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_UDP6_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets6_raw= statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_RAW6_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let sockets6_frag= statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_FRAG6_inuse").map(|((_, _, _, _), statistic)| statistic.last_value).next().unwrap_or_default();
-            let time = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_sockstat_TCP6_inuse").map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap_or_default();
-            println!("{:30} {:8} {:10.0} {:10.0} {:10.0} {:10.0}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     sockets6_tcp,
-                     sockets6_udp,
-                     sockets6_raw,
-                     sockets6_frag,
-            );
+            let sockets6_tcp = statistics.first(hostname, "node_sockstat_TCP6_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets6_udp= statistics.first(hostname, "node_sockstat_UDP6_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets6_raw= statistics.first(hostname, "node_sockstat_RAW6_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let sockets6_frag= statistics.first(hostname, "node_sockstat_FRAG6_inuse").map(|statistic| statistic.last_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_sockstat_TCP6_inuse").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                sockets6_tcp,
+                sockets6_udp,
+                sockets6_raw,
+                sockets6_frag,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_sar_n_sock6_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.0} {:10.0} {:10.0} {:10.0}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+    );
 }
 
+pub const SAR_N_SOCK6_FIELDS: &[&str] = &["tcp6sck", "udp6sck", "raw6sck", "ip6_frag"];
 pub fn print_sar_n_sock6_header()
 {
     println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "tcp6sck",
              "udp6sck",
              "raw6sck",
@@ -695,7 +754,7 @@ pub fn create_network_plots(
                 .set_label_area_size(LabelAreaPosition::Bottom, LABEL_AREA_SIZE_BOTTOM)
                 .set_label_area_size(LabelAreaPosition::Right, LABEL_AREA_SIZE_RIGHT)
                 .caption(format!("network errors per second: {} {}", filter_hostname, current_device), (CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE))
-                .build_cartesian_2d(*start_time..*end_time, low_value..high_value_overall.clone())
+                .build_cartesian_2d(*start_time..*end_time, low_value..*high_value_overall)
                 .unwrap();
             contextarea.configure_mesh()
                 .x_labels(4)
@@ -1,13 +1,14 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT};
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, MESH_STYLE_FONT, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NodeVmstatDetails {
     pub pages_swap_in: f64,
     pub pages_swap_out: f64,
@@ -20,23 +21,14 @@ pub struct NodeVmstatDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
-    let Value::Untyped(value) = sample.value else { panic!("{} value enum type should be Untyped!", sample.metric)};
+    let Value::Untyped(value) = sample.value else { return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped" }) };
     statistics
-        .entry((
-            hostname.to_string(),
-            sample.metric.clone(),
-            "".to_string(),
-            "".to_string(),
-        ))
+        .entry(hostname, &sample.metric, "", "")
         .and_modify( |row| {
-            row.delta_value = value - row.last_value;
-            row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.);
-            row.last_value = value;
-            row.last_timestamp = sample.timestamp;
-            row.first_value = false;
+            row.update_counter(value, sample.timestamp);
             debug!("{}: {} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
         } )
         .or_insert(
@@ -48,138 +40,226 @@ pub fn process_statistic(
                 ..Default::default()
             });
 
+    Ok(())
 }
 
-pub fn print_sar_w(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_w_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpin").count() > 0
+        if statistics.contains_metric(hostname, "node_vmstat_pswpin")
         {
-            let pages_swap_in = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpin").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let pages_swap_out = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpout").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let time = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpin").map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap();
-            println!("{:30} {:8} {:10.0} {:10.0}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     pages_swap_in,
-                     pages_swap_out,
-            );
+            let pages_swap_in = statistics.first(hostname, "node_vmstat_pswpin").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let pages_swap_out = statistics.first(hostname, "node_vmstat_pswpout").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let time = statistics.first(hostname, "node_vmstat_pswpin").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                pages_swap_in,
+                pages_swap_out,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
 }
 
+pub fn print_sar_w_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.0} {:10.0}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+    );
+}
+
+pub const SAR_W_FIELDS: &[&str] = &["pswpin_per_s", "pswpout_per_s"];
 pub fn print_sar_w_header()
 {
     println!("{:30} {:8} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "pswpin/s",
              "pswpout/s",
     );
 }
 
-pub fn print_sar_b(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn sar_b_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "node_vmstat_pgpgin" && !statistic.first_value).count() > 0
+        if statistics.rows(hostname, "node_vmstat_pgpgin").any(|(_, statistic)| !statistic.first_value)
         {
-            let time = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgpgin").map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap();
-
-            let pages_paged_in = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgpgin").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let pages_paged_out = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgpgout").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let faults = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgfault").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let major_faults = statistics.iter().filter(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pgmajfault").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     pages_paged_in,
-                     pages_paged_out,
-                     faults,
-                     major_faults,
-            );
+            let time = statistics.first(hostname, "node_vmstat_pgpgin").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+
+            let pages_paged_in = statistics.first(hostname, "node_vmstat_pgpgin").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let pages_paged_out = statistics.first(hostname, "node_vmstat_pgpgout").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let faults = statistics.first(hostname, "node_vmstat_pgfault").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let major_faults = statistics.first(hostname, "node_vmstat_pgmajfault").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            // the page scans and steals are not in the default fields of the node_exporter vmstat collector,
+            // they need --collector.vmstat.fields to include pgscan and pgsteal.
+            let pages_scanned_kswapd = statistics.first(hostname, "node_vmstat_pgscan_kswapd").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let pages_scanned_direct = statistics.first(hostname, "node_vmstat_pgscan_direct").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let pages_stolen = statistics.first(hostname, "node_vmstat_pgsteal_kswapd").map(|statistic| statistic.per_second_value).unwrap_or_default()
+                + statistics.first(hostname, "node_vmstat_pgsteal_direct").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            // like sar, the efficiency is 0 when no pages were scanned.
+            let pages_scanned = pages_scanned_kswapd + pages_scanned_direct;
+            let vm_efficiency = if pages_scanned > 0. { pages_stolen / pages_scanned * 100. } else { 0. };
+            let values = vec![
+                pages_paged_in,
+                pages_paged_out,
+                faults,
+                major_faults,
+                pages_scanned_kswapd,
+                pages_scanned_direct,
+                pages_stolen,
+                vm_efficiency,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_sar_b_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+    );
 }
 
+pub const SAR_B_FIELDS: &[&str] = &["pgpgin_per_s", "pgpgout_per_s", "fault_per_s", "majflt_per_s", "pgscank_per_s", "pgscand_per_s", "pgsteal_per_s", "vmeff_percent"];
 pub fn print_sar_b_header()
 {
-    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10}",
+    println!("{:30} {:8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "pgpgin/s",
              "pgpgout/s",
              "fault/s",
              "majflt/s",
+             "pgscank/s",
+             "pgscand/s",
+             "pgsteal/s",
+             "%vmeff",
     );
 }
 
-pub fn print_vmstat(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn vmstat_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, _, _), statistic)| host == hostname && metric == "node_procs_running" && !statistic.first_value)
+        if statistics.rows(hostname, "node_procs_running").any(|(_, statistic)| !statistic.first_value)
         {
-            let procs_running = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_procs_running").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let procs_blocked = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_procs_blocked").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let swap_free = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let swap_total = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_SwapTotal_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_free = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_MemFree_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_buffers = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Buffers_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let memory_cache = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Cached_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
+            let procs_running = statistics.first(hostname, "node_procs_running").map(|statistic| statistic.last_value).unwrap_or_default();
+            let procs_blocked = statistics.first(hostname, "node_procs_blocked").map(|statistic| statistic.last_value).unwrap_or_default();
+            let swap_free = statistics.first(hostname, "node_memory_SwapFree_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let swap_total = statistics.first(hostname, "node_memory_SwapTotal_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_free = statistics.first(hostname, "node_memory_MemFree_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_buffers = statistics.first(hostname, "node_memory_Buffers_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
+            let memory_cache = statistics.first(hostname, "node_memory_Cached_bytes").map(|statistic| statistic.last_value).unwrap_or_default();
             //let memory_inactive = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Inactive_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
             //let memory_active = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_memory_Active_bytes").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let swap_in = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpin").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let swap_out = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_vmstat_pswpout").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let blockdevice_in = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_read_bytes_total" && device == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let blockdevice_out = statistics.iter().find(|((host, metric, device, _), _)| host == hostname && metric == "node_disk_written_bytes_total" && device == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let interrupts = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_intr_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let context_switches = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_context_switches_total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let user_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let system_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "system" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let iowait_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "iowait" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let irq_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "irq" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let nice_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "nice" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let softirq_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "softirq" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let steal_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "steal" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let idle_time = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_seconds_total" && mode == "idle" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let guest_user = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_guest_seconds_total" && mode == "user" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let guest_nice = statistics.iter().find(|((host, metric, cpu, mode), _)| host == hostname && metric == "node_cpu_guest_seconds_total" && mode == "nice" && cpu == "total").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
+            let swap_in = statistics.first(hostname, "node_vmstat_pswpin").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let swap_out = statistics.first(hostname, "node_vmstat_pswpout").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let blockdevice_in = statistics.first_with(hostname, "node_disk_read_bytes_total", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let blockdevice_out = statistics.first_with(hostname, "node_disk_written_bytes_total", "total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let interrupts = statistics.first(hostname, "node_intr_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let context_switches = statistics.first(hostname, "node_context_switches_total").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let user_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "user").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let system_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "system").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let iowait_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "iowait").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let irq_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "irq").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let nice_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "nice").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let softirq_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "softirq").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let steal_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "steal").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let idle_time = statistics.get(hostname, "node_cpu_seconds_total", "total", "idle").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let guest_user = statistics.get(hostname, "node_cpu_guest_seconds_total", "total", "user").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let guest_nice = statistics.get(hostname, "node_cpu_guest_seconds_total", "total", "nice").map(|statistic| statistic.per_second_value).unwrap_or_default();
             let total_time = user_time + system_time + iowait_time + irq_time + nice_time + softirq_time + steal_time + idle_time + guest_user + guest_nice;
-            let time = statistics.iter().find(|((host, metric, _, _), _)| host == hostname && metric == "node_procs_running").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            println!("{:30} {:8} {:4.0} {:4.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:4.0} {:4.0} {:4.0} {:4.0} {:4.0} {:4.0}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     procs_running,
-                     procs_blocked,
-                     (swap_total-swap_free).max(0.) / (1024.*1024.),
-                     memory_free / (1024.*1024.),
-                     memory_buffers / (1024.*1024.),
-                     memory_cache / (1024. * 1024.),
-                     swap_in,
-                     swap_out,
-                     blockdevice_in / (1024. * 1024.),
-                     blockdevice_out / (1024. * 1024.),
-                     interrupts,
-                     context_switches,
-                     (user_time + nice_time) / total_time * 100.,
-                     system_time / total_time * 100.,
-                     idle_time / total_time * 100.,
-                     iowait_time / total_time * 100.,
-                     steal_time / total_time * 100.,
-                     (guest_user + guest_nice) / total_time * 100.,
-            );
+            let time = statistics.first(hostname, "node_procs_running").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                procs_running,
+                procs_blocked,
+                (swap_total-swap_free).max(0.) / (1024.*1024.),
+                memory_free / (1024.*1024.),
+                memory_buffers / (1024.*1024.),
+                memory_cache / (1024. * 1024.),
+                swap_in,
+                swap_out,
+                blockdevice_in / (1024. * 1024.),
+                blockdevice_out / (1024. * 1024.),
+                interrupts,
+                context_switches,
+                (user_time + nice_time) / total_time * 100.,
+                system_time / total_time * 100.,
+                idle_time / total_time * 100.,
+                iowait_time / total_time * 100.,
+                steal_time / total_time * 100.,
+                (guest_user + guest_nice) / total_time * 100.,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
 }
 
+pub fn print_vmstat_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:30} {:8} {:4.0} {:4.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:8.0} {:4.0} {:4.0} {:4.0} {:4.0} {:4.0} {:4.0}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+             values[8],
+             values[9],
+             values[10],
+             values[11],
+             values[12],
+             values[13],
+             values[14],
+             values[15],
+             values[16],
+             values[17],
+    );
+}
+
+pub const VMSTAT_FIELDS: &[&str] = &["procs_r", "procs_b", "memory_swpd", "memory_free", "memory_buff", "memory_cache", "swap_si", "swap_so", "io_bi", "io_bo", "system_in", "system_cs", "cpu_us", "cpu_sy", "cpu_id", "cpu_wa", "cpu_st", "cpu_gu"];
 pub fn print_vmstat_header()
 {
     println!("{:30} {:8} {:9} {:35} {:17} {:17} {:17} {:25}",
@@ -194,7 +274,7 @@ pub fn print_vmstat_header()
     );
     println!("{:30} {:8} {:>4} {:>4} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>4} {:>4} {:>4} {:>4} {:>4} {:>4}",
              "hostname",
-             "time",
+             time_header(),
              "r",
              "b",
              "swpd",
@@ -244,7 +324,7 @@ pub fn create_vmstat_plots(
         // pages swapped in/out
         let high_value_pages_swap = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| ( row.pages_swap_in + row.pages_swap_out ) )
+            .map(|(_, row)| row.pages_swap_in + row.pages_swap_out )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let filename = format!("{filter_hostname}_vmstat_pgswp.png");
@@ -268,22 +348,22 @@ pub fn create_vmstat_plots(
 
         let min_pgswpin = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_swap_in )
+            .map(|(_, row)| row.pages_swap_in )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_pgswpin = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_swap_in )
+            .map(|(_, row)| row.pages_swap_in )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let min_pgswpout = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_swap_out )
+            .map(|(_, row)| row.pages_swap_out )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_pgswpout = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_swap_out )
+            .map(|(_, row)| row.pages_swap_out )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         // pgswapout = pgswapin + pgswapout
@@ -313,7 +393,7 @@ pub fn create_vmstat_plots(
         // pages paged in/out
         let high_value_pages_paged = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| ( row.pages_page_in + row.pages_page_out ) )
+            .map(|(_, row)| row.pages_page_in + row.pages_page_out )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let filename = format!("{filter_hostname}_vmstat_paging.png");
@@ -337,22 +417,22 @@ pub fn create_vmstat_plots(
 
         let min_pgpgin = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_page_in )
+            .map(|(_, row)| row.pages_page_in )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_pgpgin = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_page_in )
+            .map(|(_, row)| row.pages_page_in )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let min_pgpgout = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_page_out )
+            .map(|(_, row)| row.pages_page_out )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_pgpgout = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_page_out )
+            .map(|(_, row)| row.pages_page_out )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         // pgpgout = pgspgin + pgpgout
@@ -382,7 +462,7 @@ pub fn create_vmstat_plots(
         //  pagefaults
         let high_value_pagefaults = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| ( row.pages_minor_pagefault + row.pages_major_pagefault ) )
+            .map(|(_, row)| row.pages_minor_pagefault + row.pages_major_pagefault )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let filename = format!("{filter_hostname}_vmstat_pagefaults.png");
@@ -406,22 +486,22 @@ pub fn create_vmstat_plots(
 
         let min_minor_fault = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_minor_pagefault )
+            .map(|(_, row)| row.pages_minor_pagefault )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_minor_fault = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_minor_pagefault )
+            .map(|(_, row)| row.pages_minor_pagefault )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let min_major_fault = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_major_pagefault )
+            .map(|(_, row)| row.pages_major_pagefault )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_major_fault = unlocked_historical_data.vmstat_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.pages_major_pagefault )
+            .map(|(_, row)| row.pages_major_pagefault )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         // minor faults = minor faults + major faults
@@ -464,17 +544,17 @@ pub fn create_vmstat_plots(
         let low_value: f64 = 0.0;
         let high_value_load_1 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.load_1)
+            .map(|(_, row)| row.load_1)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_load_5 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.load_5)
+            .map(|(_, row)| row.load_5)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value_load_15 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.load_15)
+            .map(|(_, row)| row.load_15)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let high_value = vec![high_value_load_1, high_value_load_5, high_value_load_15].into_iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
@@ -499,12 +579,12 @@ pub fn create_vmstat_plots(
 
         let min_load_1 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_1 )
+            .map(|(_, row)| row.load_1 )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_load_1 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_1 )
+            .map(|(_, row)| row.load_1 )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -518,12 +598,12 @@ pub fn create_vmstat_plots(
 
         let min_load_5 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_5 )
+            .map(|(_, row)| row.load_5 )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_load_5 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_5 )
+            .map(|(_, row)| row.load_5 )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -537,12 +617,12 @@ pub fn create_vmstat_plots(
 
         let min_load_15 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_15 )
+            .map(|(_, row)| row.load_15 )
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_load_15 = unlocked_historical_data.misc_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname )
-            .map(|((_, _), row)| row.load_15 )
+            .map(|(_, row)| row.load_15 )
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(LineSeries::new(unlocked_historical_data.misc_details.iter()
@@ -565,3 +645,33 @@ pub fn create_vmstat_plots(
          */
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sar_b_statistics(
+        counters: &[(&str, f64)],
+    ) -> Statistics
+    {
+        let mut statistics = Statistics::default();
+        for (metric, per_second_value) in counters
+        {
+            statistics.entry("node1:9100:metrics", metric, "", "").or_insert(Statistic { per_second_value: *per_second_value, ..Default::default() });
+        }
+        statistics
+    }
+
+    #[test]
+    fn sar_b_vm_efficiency() {
+        let statistics = sar_b_statistics(&[("node_vmstat_pgpgin", 10.), ("node_vmstat_pgscan_kswapd", 300.), ("node_vmstat_pgscan_direct", 100.), ("node_vmstat_pgsteal_kswapd", 250.), ("node_vmstat_pgsteal_direct", 50.)]);
+        let rows = sar_b_rows(&statistics);
+        assert_eq!(rows[0].values[4..], [300., 100., 300., 75.]);
+    }
+
+    #[test]
+    fn sar_b_vm_efficiency_without_scans() {
+        let rows = sar_b_rows(&sar_b_statistics(&[("node_vmstat_pgpgin", 10.)]));
+        assert_eq!(rows[0].values[4..], [0., 0., 0., 0.]);
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use clap::ValueEnum;
+use serde_json::Value;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::Row;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat
+{
+    Text,
+    Json,
+    Ndjson,
+}
+
+/// What the machine readable rows of a view are made of: the name of the view, the name of the label column, if any, and the names of the values.
+pub struct View {
+    pub name: String,
+    pub label_name: Option<&'static str>,
+    pub fields: &'static [&'static str],
+}
+
+impl View {
+    /// The extra labels of a row that can be added to it: a label with the name of a member of the view is left out, and so is a second label with the same name.
+    pub fn extra_labels<'a>(
+        &'a self,
+        labels: &'a [(String, String)],
+    ) -> Vec<&'a (String, String)>
+    {
+        let mut names: Vec<&str> = vec!["view", "host", "timestamp", "summary"];
+        names.extend(self.label_name);
+        names.extend(self.fields.iter());
+        labels.iter()
+            .filter(|(name, _)| if names.contains(&name.as_str()) { false } else { names.push(name); true })
+            .collect()
+    }
+}
+
+/// Writes the rows of the views as JSON objects: one per line for ndjson, or as a single array for json.
+/// The array is opened with the first row and closed by finish, so it can be written while the run goes on.
+pub struct RowWriter {
+    format: OutputFormat,
+    writer: Box<dyn Write + Send>,
+    rows_written: u64,
+}
+
+impl RowWriter {
+    pub fn new(
+        format: OutputFormat,
+        file: Option<&str>,
+    ) -> Result<Self>
+    {
+        let writer: Box<dyn Write + Send> = match file
+        {
+            Some(file) => Box::new(BufWriter::new(File::create(file).with_context(|| format!("creating output file {}", file))?)),
+            None => Box::new(io::stdout()),
+        };
+        Ok(RowWriter { format, writer, rows_written: 0 })
+    }
+    pub fn write_row(
+        &mut self,
+        view: &View,
+        row: &Row,
+    ) -> Result<()>
+    {
+        let timestamp = row.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        self.write_object(view, ("timestamp", &timestamp), &row.hostname, &row.label, &row.labels, &row.values)
+    }
+    /// A row of the summary at the end of the run, which has the kind of summary (average, min or max) instead of a timestamp.
+    pub fn write_summary(
+        &mut self,
+        view: &View,
+        summary: &str,
+        hostname: &str,
+        label: &str,
+        labels: &[(String, String)],
+        values: &[f64],
+    ) -> Result<()>
+    {
+        self.write_object(view, ("summary", summary), hostname, label, labels, values)
+    }
+    fn write_object(
+        &mut self,
+        view: &View,
+        time: (&str, &str),
+        hostname: &str,
+        label: &str,
+        labels: &[(String, String)],
+        values: &[f64],
+    ) -> Result<()>
+    {
+        // the object is built as text, because a serde_json map would sort the keys, and the fields should be in the order of the view.
+        let mut members = vec![
+            format!("\"view\":{}", Value::from(view.name.as_str())),
+            format!("\"host\":{}", Value::from(hostname)),
+            format!("{}:{}", Value::from(time.0), Value::from(time.1)),
+        ];
+        if let Some(label_name) = view.label_name
+        {
+            members.push(format!("{}:{}", Value::from(label_name), Value::from(label)));
+        }
+        members.extend(view.extra_labels(labels).into_iter().map(|(name, value)| format!("{}:{}", Value::from(name.as_str()), Value::from(value.as_str()))));
+        // a value that is not a number, such as a division by zero, is written as null.
+        members.extend(view.fields.iter().zip(values).map(|(field, value)| format!("{}:{}", Value::from(*field), Value::from(*value))));
+        self.write_line(&format!("{{{}}}", members.join(",")))
+    }
+    /// A marker of a host in an interval, such as a failed scrape, as an object of a view of its own with the members given,
+    /// like {"view":"target-health","host":..,"timestamp":..,"status":"failed",..}.
+    pub fn write_marker(
+        &mut self,
+        view_name: &str,
+        hostname: &str,
+        timestamp: DateTime<Utc>,
+        marker: &[(&str, Value)],
+    ) -> Result<()>
+    {
+        let mut members = vec![
+            format!("\"view\":{}", Value::from(view_name)),
+            format!("\"host\":{}", Value::from(hostname)),
+            format!("\"timestamp\":{}", Value::from(timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))),
+        ];
+        members.extend(marker.iter().map(|(name, value)| format!("{}:{}", Value::from(*name), value)));
+        self.write_line(&format!("{{{}}}", members.join(",")))
+    }
+    fn write_line(
+        &mut self,
+        object: &str,
+    ) -> Result<()>
+    {
+        match self.format
+        {
+            OutputFormat::Json => {
+                let separator = if self.rows_written == 0 { "[\n" } else { ",\n" };
+                write!(self.writer, "{}{}", separator, object)?;
+            },
+            _ => writeln!(self.writer, "{}", object)?,
+        }
+        self.rows_written += 1;
+        Ok(())
+    }
+    /// Flush the rows of an interval, so a reader of the stream gets them without waiting for the next.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().context("writing output")
+    }
+    pub fn finish(&mut self) -> Result<()> {
+        if self.format == OutputFormat::Json
+        {
+            let end = if self.rows_written == 0 { "[]\n" } else { "\n]\n" };
+            write!(self.writer, "{}", end)?;
+        }
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sar_d_view() -> View {
+        View { name: "sar-d".to_string(), label_name: Some("device"), fields: &["tps", "await"] }
+    }
+
+    // the rows written with a row writer of the format given, as read back from its output file.
+    fn write_rows(
+        format: OutputFormat,
+        rows: &[Row],
+    ) -> String
+    {
+        let path = std::env::temp_dir().join(format!("dsar-test-{}-rows-{:?}", std::process::id(), format));
+        let mut row_writer = RowWriter::new(format, Some(&path.to_string_lossy())).unwrap();
+        for row in rows
+        {
+            row_writer.write_row(&sar_d_view(), row).unwrap();
+        }
+        row_writer.finish().unwrap();
+        drop(row_writer);
+        let output = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        output
+    }
+
+    fn rows() -> Vec<Row> {
+        let timestamp = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        vec![
+            Row { hostname: "node1:9100:metrics".to_string(), timestamp, label: "sda".to_string(), labels: vec![("env".to_string(), "prod".to_string())], values: vec![10., 1.5] },
+            Row { hostname: "node1:9100:metrics".to_string(), timestamp, label: "sdb".to_string(), labels: Vec::new(), values: vec![0., f64::NAN] },
+        ]
+    }
+
+    #[test]
+    fn ndjson_framing() {
+        let output = write_rows(OutputFormat::Ndjson, &rows());
+        assert_eq!(output, concat!(
+            r#"{"view":"sar-d","host":"node1:9100:metrics","timestamp":"2026-10-17T12:00:00.000Z","device":"sda","env":"prod","tps":10.0,"await":1.5}"#, "\n",
+            r#"{"view":"sar-d","host":"node1:9100:metrics","timestamp":"2026-10-17T12:00:00.000Z","device":"sdb","tps":0.0,"await":null}"#, "\n",
+        ));
+        for line in output.lines()
+        {
+            serde_json::from_str::<Value>(line).unwrap();
+        }
+    }
+
+    #[test]
+    fn json_framing() {
+        let output = write_rows(OutputFormat::Json, &rows());
+        let array: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(array.as_array().map(|rows| rows.len()), Some(2));
+        assert_eq!(array[0]["device"], "sda");
+        assert_eq!(array[1]["await"], Value::Null);
+        assert!(output.starts_with("[\n{") && output.ends_with("}\n]\n"));
+    }
+
+    #[test]
+    fn json_framing_without_rows() {
+        assert_eq!(write_rows(OutputFormat::Json, &[]), "[]\n");
+        assert_eq!(write_rows(OutputFormat::Ndjson, &[]), "");
+    }
+
+    #[test]
+    fn extra_labels_clashing_with_members() {
+        let labels = vec![
+            ("host".to_string(), "other".to_string()),
+            ("device".to_string(), "sdz".to_string()),
+            ("tps".to_string(), "1".to_string()),
+            ("env".to_string(), "prod".to_string()),
+            ("env".to_string(), "test".to_string()),
+        ];
+        let view = sar_d_view();
+        assert_eq!(view.extra_labels(&labels), vec![&("env".to_string(), "prod".to_string())]);
+    }
+}
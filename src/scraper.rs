@@ -0,0 +1,369 @@
+use std::{collections::{BTreeMap, HashMap}, hash::{DefaultHasher, Hash, Hasher}, sync::{Arc, Mutex}, time::Duration};
+use tokio::{sync::Semaphore, task::JoinSet, time::{self, Instant}};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use anyhow::Result;
+use log::*;
+
+use crate::{RawScrape, ScrapeOptions, format_time};
+use crate::target::Target;
+use crate::output::RowWriter;
+
+/// The health of a target, as seen by the scrapes.
+#[derive(Debug, Default, Clone)]
+pub struct TargetHealth {
+    pub scrapes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u64,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub last_duration: Duration,
+    pub total_duration: Duration,
+    /// A failing target is not scraped again until this time.
+    pub next_attempt: Option<Instant>,
+}
+
+impl TargetHealth {
+    fn record(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        outcome: &ScrapeOutcome,
+        duration: Duration,
+    )
+    {
+        self.scrapes += 1;
+        self.last_duration = duration;
+        self.total_duration += duration;
+        match outcome
+        {
+            ScrapeOutcome::Success(status) => {
+                self.last_status = Some(*status);
+                self.last_success = Some(timestamp);
+                self.last_error = None;
+                self.consecutive_failures = 0;
+                self.next_attempt = None;
+            },
+            ScrapeOutcome::Failure(status, error) => {
+                self.last_status = *status;
+                self.last_failure = Some(timestamp);
+                self.last_error = Some(error.to_string());
+                self.failures += 1;
+                self.consecutive_failures += 1;
+            },
+        }
+    }
+    /// True if the last scrape of the target failed.
+    pub fn failed(&self) -> bool {
+        self.consecutive_failures > 0
+    }
+    /// True if the target is skipped, because it is failing.
+    pub fn backing_off(&self) -> bool {
+        self.next_attempt.map(|next_attempt| next_attempt > Instant::now()).unwrap_or(false)
+    }
+}
+
+// the result of a single scrape: the http status, and the reason for a failure.
+#[derive(Debug)]
+enum ScrapeOutcome {
+    Success(u16),
+    Failure(Option<u16>, String),
+}
+
+// the time until a failing target is scraped again: the interval, doubled for every consecutive failure after the first, up to the maximum.
+// the jitter (between 80% and 120%) spreads the probes of targets that went down at the same time.
+// it is taken from a hash of the target and the number of failures, so it differs per target and per probe, but is reproducible.
+fn backoff(
+    target: &str,
+    consecutive_failures: u64,
+    interval: Duration,
+    backoff_max: Duration,
+) -> Duration
+{
+    let backoff = interval.saturating_mul(2_u32.saturating_pow(consecutive_failures.saturating_sub(1).min(31) as u32)).min(backoff_max);
+    let mut hasher = DefaultHasher::new();
+    (target, consecutive_failures).hash(&mut hasher);
+    backoff.mul_f64(0.8 + (hasher.finish() % 1000) as f64 / 1000. * 0.4)
+}
+
+/// A long-lived scraper: the client (and therefore its connections) is kept between the intervals.
+/// The number of concurrent scrapes is limited in total and per host,
+/// and the scrapes that have not finished by the deadline are abandoned, so the next interval starts on time.
+/// A failing target is scraped less often (backoff), until it responds again.
+pub struct Scraper {
+    client: reqwest::Client,
+    scrape_options: Arc<ScrapeOptions>,
+    parallel: Arc<Semaphore>,
+    parallel_per_host: usize,
+    host_semaphores: Mutex<HashMap<String, Arc<Semaphore>>>,
+    interval: Duration,
+    backoff_max: Duration,
+    health: Arc<Mutex<BTreeMap<String, TargetHealth>>>,
+}
+
+impl Scraper {
+    pub fn new(
+        scrape_options: &ScrapeOptions,
+        parallel: usize,
+        parallel_per_host: usize,
+        interval: Duration,
+        backoff_max: Duration,
+    ) -> Result<Self>
+    {
+        Ok(Self {
+            client: scrape_options.async_client()?,
+            scrape_options: Arc::new(scrape_options.clone()),
+            parallel: Arc::new(Semaphore::new(parallel)),
+            parallel_per_host,
+            host_semaphores: Mutex::new(HashMap::new()),
+            interval,
+            backoff_max,
+            health: Arc::new(Mutex::new(BTreeMap::new())),
+        })
+    }
+    /// Scrape all targets, and return the responses by target name (host:port:path).
+    /// A target that fails or doesn't respond before the deadline returns no data, a target that is backing off is not returned.
+    pub async fn scrape(
+        &self,
+        targets: &[Target],
+    ) -> HashMap<String, RawScrape>
+    {
+        // the scrapes must be done in time to process and print the statistics before the next interval.
+        let deadline = Instant::now() + self.interval.mul_f64(0.9);
+        let mut scrapes = JoinSet::new();
+        for target in targets
+        {
+            if self.health.lock().unwrap().get(&target.name()).map(|health| health.backing_off()).unwrap_or(false)
+            {
+                debug!("Backing off: {}", target.url());
+                continue;
+            }
+            let client = self.client.clone();
+            let scrape_options = self.scrape_options.clone();
+            let parallel = self.parallel.clone();
+            let host_semaphore = self.host_semaphore(&target.host);
+            let target = target.clone();
+            scrapes.spawn(async move {
+                let start = Instant::now();
+                let (raw_scrape, outcome) = match time::timeout_at(deadline, async {
+                    let _permit = parallel.acquire().await.expect("scraper semaphore closed");
+                    let _host_permit = host_semaphore.acquire().await.expect("scraper host semaphore closed");
+                    read_node_exporter(&client, &target, &scrape_options).await
+                }).await
+                {
+                    Ok(result) => result,
+                    Err(_) => {
+                        debug!("Deadline exceeded: {}", target.url());
+                        (RawScrape { timestamp: Utc::now(), data: String::new() }, ScrapeOutcome::Failure(None, "deadline exceeded".to_string()))
+                    },
+                };
+                (target.name(), raw_scrape, outcome, start.elapsed())
+            });
+        }
+        let mut map_exporter_values: HashMap<String, RawScrape> = HashMap::new();
+        while let Some(scrape) = scrapes.join_next().await
+        {
+            match scrape
+            {
+                Ok((hostname_port, node_exporter_values, outcome, duration)) => {
+                    let mut health = self.health.lock().unwrap();
+                    let target_health = health.entry(hostname_port.clone()).or_default();
+                    let previous_failures = target_health.consecutive_failures;
+                    target_health.record(node_exporter_values.timestamp, &outcome, duration);
+                    // a single failure can be a hiccup: a target is only skipped after it failed twice in a row.
+                    if target_health.consecutive_failures > 1 && !self.backoff_max.is_zero()
+                    {
+                        target_health.next_attempt = Some(Instant::now() + backoff(&hostname_port, target_health.consecutive_failures, self.interval, self.backoff_max));
+                    }
+                    if previous_failures > 0 && !target_health.failed()
+                    {
+                        info!("target {} responds again, after {} failed scrape(s)", hostname_port, previous_failures);
+                    }
+                    map_exporter_values.insert(hostname_port, node_exporter_values);
+                },
+                Err(error) => error!("scrape task failed: {}", error),
+            }
+        }
+        // the targets that are not scraped anymore (target file or discovery changes) are not reported, and their hosts are not limited anymore.
+        let target_names: Vec<String> = targets.iter().map(|target| target.name()).collect();
+        self.health.lock().unwrap().retain(|hostname_port, _| target_names.contains(hostname_port));
+        self.host_semaphores.lock().unwrap().retain(|host, _| targets.iter().any(|target| &target.host == host));
+        map_exporter_values
+    }
+    /// The health of the targets, shared so it can be reported at exit.
+    pub fn health(&self) -> Arc<Mutex<BTreeMap<String, TargetHealth>>> {
+        self.health.clone()
+    }
+    fn host_semaphore(
+        &self,
+        host: &str,
+    ) -> Arc<Semaphore>
+    {
+        self.host_semaphores.lock().unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.parallel_per_host)))
+            .clone()
+    }
+}
+
+async fn read_node_exporter(
+    client: &reqwest::Client,
+    target: &Target,
+    scrape_options: &ScrapeOptions,
+) -> (RawScrape, ScrapeOutcome)
+{
+    let (response, outcome) = match scrape_options.authenticate(client.get(target.url())).send().await
+    {
+        Ok(data_from_http) => {
+            let status = data_from_http.status();
+            if ! &status.is_success()
+            {
+                debug!("Non success response: {} = {}", target.url(), &status);
+            }
+            else
+            {
+                debug!("Success response: {} = {}", target.url(), &status);
+            }
+            match data_from_http.text().await
+            {
+                Ok(text) if status.is_success() => (text, ScrapeOutcome::Success(status.as_u16())),
+                // the body of a non success response is not a prometheus scrape.
+                Ok(_) => (String::new(), ScrapeOutcome::Failure(Some(status.as_u16()), format!("HTTP {}", status))),
+                Err(error) => {
+                    debug!("Error reading response: {}: {}", target.url(), error);
+                    (String::new(), ScrapeOutcome::Failure(Some(status.as_u16()), "error reading response".to_string()))
+                },
+            }
+        },
+        Err(error) => {
+            debug!("Non-Ok success response: {}: {}", target.url(), error);
+            let reason = if error.is_connect() { "connect error" } else if error.is_timeout() { "timeout" } else { "request error" };
+            (String::new(), ScrapeOutcome::Failure(None, reason.to_string()))
+        },
+    };
+    (RawScrape { timestamp: Utc::now(), data: response }, outcome)
+}
+
+/// Print a marker row for every target whose scrape failed this interval.
+/// Targets that never had a successful scrape are only reported if report_never_succeeded is set:
+/// with the hostname and port combinations, most of the endpoints don't exist.
+pub fn print_failed_targets(
+    health: &BTreeMap<String, TargetHealth>,
+    report_never_succeeded: bool,
+)
+{
+    for (hostname, target_health) in health.iter().filter(|(_, target_health)| target_health.failed() && (report_never_succeeded || target_health.last_success.is_some()))
+    {
+        println!("{:30} {:8} ** scrape failed: {}, {} consecutive failure(s), last success: {}{} **",
+                 hostname,
+                 target_health.last_failure.map(format_time).unwrap_or_default(),
+                 target_health.last_error.as_deref().unwrap_or_default(),
+                 target_health.consecutive_failures,
+                 target_health.last_success.map(format_time).unwrap_or_else(|| "never".to_string()),
+                 target_health.next_attempt.filter(|_| target_health.backing_off()).map(|next_attempt| format!(", backing off, next probe in {}s", next_attempt.saturating_duration_since(Instant::now()).as_secs_f64().ceil())).unwrap_or_default(),
+        );
+    }
+}
+
+/// Write a target-health row for every target that failed this interval, the machine readable version of print_failed_targets.
+pub fn write_failed_targets(
+    health: &BTreeMap<String, TargetHealth>,
+    report_never_succeeded: bool,
+    row_writer: &mut RowWriter,
+) -> Result<()>
+{
+    for (hostname, target_health) in health.iter().filter(|(_, target_health)| target_health.failed() && (report_never_succeeded || target_health.last_success.is_some()))
+    {
+        row_writer.write_marker("target-health", hostname, target_health.last_failure.unwrap_or_else(Utc::now), &[
+            ("status", Value::from("failed")),
+            ("error", Value::from(target_health.last_error.as_deref())),
+            ("consecutive_failures", Value::from(target_health.consecutive_failures)),
+            ("last_success", Value::from(target_health.last_success.map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true)))),
+            ("backing_off", Value::from(target_health.backing_off())),
+        ])?;
+    }
+    Ok(())
+}
+
+/// Print the health of the targets, for the summary at exit.
+pub fn print_target_health_summary(
+    health: &BTreeMap<String, TargetHealth>,
+    report_never_succeeded: bool,
+)
+{
+    println!("{:30} {:>8} {:>8} {:>8} {:>6} {:>9} {:>9} {:19} last error",
+             "target",
+             "scrapes",
+             "failures",
+             "consec",
+             "status",
+             "last(ms)",
+             "avg(ms)",
+             "last success",
+    );
+    for (hostname, target_health) in health.iter().filter(|(_, target_health)| report_never_succeeded || target_health.last_success.is_some())
+    {
+        println!("{:30} {:8} {:8} {:8} {:>6} {:9.1} {:9.1} {:19} {}",
+                 hostname,
+                 target_health.scrapes,
+                 target_health.failures,
+                 target_health.consecutive_failures,
+                 target_health.last_status.map(|status| status.to_string()).unwrap_or_else(|| "-".to_string()),
+                 target_health.last_duration.as_secs_f64() * 1000.,
+                 target_health.total_duration.as_secs_f64() * 1000. / target_health.scrapes.max(1) as f64,
+                 target_health.last_success.map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_else(|| "never".to_string()),
+                 target_health.last_error.as_deref().unwrap_or("-"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn host_semaphores_follow_the_targets() {
+        let scraper = Scraper::new(&ScrapeOptions::default(), 3, 2, Duration::from_millis(500), Duration::ZERO).unwrap();
+        // nothing listens on port 1, so the scrapes fail right away.
+        let targets = vec![Target::parse("127.0.0.1:1", "http").unwrap(), Target::parse("localhost:1", "http").unwrap()];
+        scraper.scrape(&targets).await;
+        assert_eq!(scraper.host_semaphores.lock().unwrap().len(), 2);
+
+        scraper.scrape(&targets[..1]).await;
+        assert_eq!(scraper.host_semaphores.lock().unwrap().keys().collect::<Vec<_>>(), vec!["127.0.0.1"]);
+        assert_eq!(scraper.health().lock().unwrap().keys().collect::<Vec<_>>(), vec!["127.0.0.1:1:metrics"]);
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter() {
+        let interval = Duration::from_secs(1);
+        let backoff_max = Duration::from_secs(60);
+        for consecutive_failures in 1..=5
+        {
+            let base = interval * 2_u32.pow(consecutive_failures as u32 - 1);
+            let backoff = backoff("node1:9100:metrics", consecutive_failures, interval, backoff_max);
+            assert!(backoff >= base.mul_f64(0.8) && backoff <= base.mul_f64(1.2), "failures: {}, backoff: {:?}", consecutive_failures, backoff);
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let backoff_max = Duration::from_secs(60);
+        for consecutive_failures in [7, 20, 64, u64::MAX]
+        {
+            let backoff = backoff("node1:9100:metrics", consecutive_failures, Duration::from_secs(1), backoff_max);
+            assert!(backoff >= backoff_max.mul_f64(0.8) && backoff <= backoff_max.mul_f64(1.2), "failures: {}, backoff: {:?}", consecutive_failures, backoff);
+        }
+    }
+
+    #[test]
+    fn backoff_jitter_is_deterministic_per_target() {
+        let interval = Duration::from_secs(1);
+        let backoff_max = Duration::from_secs(60);
+        assert_eq!(backoff("node1:9100:metrics", 3, interval, backoff_max), backoff("node1:9100:metrics", 3, interval, backoff_max));
+        // targets that failed together are spread out, so they are not all retried in the same interval.
+        let backoffs: Vec<Duration> = (1..=10).map(|node| backoff(&format!("node{}:9100:metrics", node), 3, interval, backoff_max)).collect();
+        assert!(backoffs.iter().any(|backoff| *backoff != backoffs[0]));
+    }
+}
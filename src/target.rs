@@ -0,0 +1,273 @@
+use std::{collections::BTreeMap, fs, path::Path, time::SystemTime};
+use anyhow::{anyhow, Context, Result};
+use reqwest::Url;
+use serde::Deserialize;
+use log::*;
+
+/// A single endpoint to scrape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Target {
+    /// Parse a target in the form [scheme://]host:port[/path].
+    /// Without a scheme the default scheme is used, without a path the node_exporter path: metrics.
+    pub fn parse(
+        target: &str,
+        default_scheme: &str,
+    ) -> Result<Self>
+    {
+        let url = if target.contains("://") { Url::parse(target) } else { Url::parse(&format!("{}://{}", default_scheme, target)) }
+            .with_context(|| format!("invalid target: {}", target))?;
+        let host = url.host_str().ok_or_else(|| anyhow!("invalid target: {}: no host", target))?;
+        // the port is required: node_exporter and YugabyteDB don't listen on 80 or 443.
+        // url leaves out a port that is the default for the scheme, so an explicit :80 or :443 is taken from the authority.
+        let authority = target.split_once("://").map(|(_, rest)| rest).unwrap_or(target).split('/').next().unwrap_or_default();
+        let explicit_port = authority.rsplit_once(':').map(|(_, port)| !port.is_empty() && port.chars().all(|character| character.is_ascii_digit())).unwrap_or(false);
+        let port = url.port().or_else(|| if explicit_port { url.port_or_known_default() } else { None })
+            .ok_or_else(|| anyhow!("invalid target: {}: no port", target))?;
+        let path = url.path().trim_start_matches('/');
+        Ok(Self {
+            scheme: url.scheme().to_string(),
+            host: host.to_string(),
+            port,
+            path: if path.is_empty() { "metrics".to_string() } else { path.to_string() },
+            labels: BTreeMap::new(),
+        })
+    }
+    /// The name that identifies the target in the output: host:port:path.
+    pub fn name(&self) -> String {
+        format!("{}:{}:{}", self.host, self.port, self.path)
+    }
+    pub fn url(&self) -> String {
+        format!("{}://{}:{}/{}", self.scheme, self.host, self.port, self.path)
+    }
+}
+
+/// The targets for every combination of hosts, ports and the node_exporter and YugabyteDB endpoints.
+/// A host can be prefixed with the scheme, like https://hostname.
+pub fn targets_from_hosts_ports(
+    hosts: &[&str],
+    ports: &[&str],
+    default_scheme: &str,
+) -> Result<Vec<Target>>
+{
+    let mut targets = Vec::new();
+    for host in hosts
+    {
+        let (scheme, host) = host.split_once("://").unwrap_or((default_scheme, host));
+        for port in ports
+        {
+            for endpoint in ["metrics", "prometheus-metrics"]
+            {
+                targets.push(Target {
+                    scheme: scheme.to_string(),
+                    host: host.to_string(),
+                    port: port.parse().with_context(|| format!("invalid port: {}", port))?,
+                    path: endpoint.to_string(),
+                    labels: BTreeMap::new(),
+                });
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Read the targets from a file, one target per line. Empty lines and lines starting with # are skipped.
+pub fn read_target_file(
+    file: &str,
+    default_scheme: &str,
+) -> Result<Vec<Target>>
+{
+    let contents = fs::read_to_string(file).with_context(|| format!("reading target file {}", file))?;
+    contents.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Target::parse(line, default_scheme).with_context(|| format!("target file {}", file)))
+        .collect()
+}
+
+// a target group in a prometheus file_sd_configs file.
+#[derive(Debug, Deserialize)]
+struct FileSdGroup {
+    targets: Vec<String>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+/// Read the targets from a prometheus file_sd_configs file, in json, or in yaml if the file name ends in .yml or .yaml.
+/// The __scheme__ and __metrics_path__ labels set the scheme and path of the targets in a group,
+/// the other labels starting with __ are prometheus internal and are skipped.
+pub fn read_file_sd(
+    file: &str,
+    default_scheme: &str,
+) -> Result<Vec<Target>>
+{
+    let contents = fs::read_to_string(file).with_context(|| format!("reading file_sd file {}", file))?;
+    let groups: Vec<FileSdGroup> = if file.ends_with(".yml") || file.ends_with(".yaml")
+    {
+        serde_yaml::from_str(&contents).with_context(|| format!("parsing file_sd file {}", file))?
+    }
+    else
+    {
+        serde_json::from_str(&contents).with_context(|| format!("parsing file_sd file {}", file))?
+    };
+    let mut targets = Vec::new();
+    for group in groups
+    {
+        let scheme = group.labels.get("__scheme__").map(String::as_str).unwrap_or(default_scheme);
+        let labels: BTreeMap<String, String> = group.labels.iter().filter(|(label, _)| !label.starts_with("__")).map(|(label, value)| (label.clone(), value.clone())).collect();
+        for target in &group.targets
+        {
+            let mut target = Target::parse(target, scheme).with_context(|| format!("file_sd file {}", file))?;
+            if let Some(path) = group.labels.get("__metrics_path__")
+            {
+                target.path = path.trim_start_matches('/').to_string();
+            }
+            target.labels = labels.clone();
+            targets.push(target);
+        }
+    }
+    Ok(targets)
+}
+
+/// A file with targets, which is read again when it is changed.
+pub struct TargetFile {
+    path: String,
+    file_sd: bool,
+    default_scheme: String,
+    modified: Option<SystemTime>,
+    pub targets: Vec<Target>,
+}
+
+impl TargetFile {
+    pub fn new(
+        path: &str,
+        file_sd: bool,
+        default_scheme: &str,
+    ) -> Result<Self>
+    {
+        let mut target_file = Self {
+            path: path.to_string(),
+            file_sd,
+            default_scheme: default_scheme.to_string(),
+            modified: None,
+            targets: Vec::new(),
+        };
+        target_file.reload_if_changed()?;
+        Ok(target_file)
+    }
+    /// Read the file again if its modification time changed. Returns true if the targets were read.
+    /// When the file can't be read or parsed, the current targets stay in use.
+    pub fn reload_if_changed(
+        &mut self,
+    ) -> Result<bool>
+    {
+        let modified = fs::metadata(Path::new(&self.path)).and_then(|metadata| metadata.modified()).with_context(|| format!("reading target file {}", self.path))?;
+        if self.modified == Some(modified) { return Ok(false) };
+        let targets = if self.file_sd { read_file_sd(&self.path, &self.default_scheme)? } else { read_target_file(&self.path, &self.default_scheme)? };
+        if self.modified.is_some()
+        {
+            info!("target file {} changed: {} targets (was {})", self.path, targets.len(), self.targets.len());
+        }
+        for target in &targets
+        {
+            debug!("target file {}: target: {}, labels: {:?}", self.path, target.url(), target.labels);
+        }
+        self.modified = Some(modified);
+        self.targets = targets;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a file in the temp directory, unique for this test run, with the contents given.
+    fn write_temp_file(
+        name: &str,
+        contents: &str,
+    ) -> String
+    {
+        let path = std::env::temp_dir().join(format!("dsar-test-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn parse_host_port() {
+        let target = Target::parse("node1:9100", "http").unwrap();
+        assert_eq!(target.scheme, "http");
+        assert_eq!(target.host, "node1");
+        assert_eq!(target.port, 9100);
+        assert_eq!(target.path, "metrics");
+        assert_eq!(target.name(), "node1:9100:metrics");
+        assert_eq!(target.url(), "http://node1:9100/metrics");
+    }
+
+    #[test]
+    fn parse_scheme_and_path() {
+        let target = Target::parse("https://node1:9000/prometheus-metrics", "http").unwrap();
+        assert_eq!(target.scheme, "https");
+        assert_eq!(target.port, 9000);
+        assert_eq!(target.path, "prometheus-metrics");
+    }
+
+    #[test]
+    fn parse_requires_a_port() {
+        assert!(Target::parse("node1", "http").is_err());
+        assert!(Target::parse("https://node1/metrics", "http").is_err());
+        assert!(Target::parse("node1:", "http").is_err());
+    }
+
+    #[test]
+    fn parse_explicit_default_port() {
+        assert_eq!(Target::parse("node1:80", "http").unwrap().port, 80);
+        assert_eq!(Target::parse("https://node1:443/metrics", "http").unwrap().port, 443);
+    }
+
+    #[test]
+    fn file_sd_json() {
+        let file = write_temp_file("file_sd.json", r#"[
+            { "targets": [ "node1:9100", "node2:9100" ], "labels": { "env": "prod", "__meta_dc": "dc1" } },
+            { "targets": [ "node3:9000" ], "labels": { "__scheme__": "https", "__metrics_path__": "/prometheus-metrics" } }
+        ]"#);
+        let targets = read_file_sd(&file, "http").unwrap();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(targets.iter().map(|target| target.url()).collect::<Vec<_>>(), vec![
+            "http://node1:9100/metrics",
+            "http://node2:9100/metrics",
+            "https://node3:9000/prometheus-metrics",
+        ]);
+        // the labels starting with __ are not target labels.
+        assert_eq!(targets[0].labels, BTreeMap::from([("env".to_string(), "prod".to_string())]));
+        assert_eq!(targets[1].labels, targets[0].labels);
+        assert!(targets[2].labels.is_empty());
+    }
+
+    #[test]
+    fn file_sd_yaml() {
+        let file = write_temp_file("file_sd.yml", "- targets: [ 'node1:9100' ]\n  labels:\n    env: test\n");
+        let targets = read_file_sd(&file, "http").unwrap();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name(), "node1:9100:metrics");
+        assert_eq!(targets[0].labels.get("env").map(String::as_str), Some("test"));
+    }
+
+    #[test]
+    fn file_sd_target_without_port() {
+        let file = write_temp_file("file_sd_no_port.json", r#"[ { "targets": [ "node1" ] } ]"#);
+        let result = read_file_sd(&file, "http");
+        fs::remove_file(&file).unwrap();
+        assert!(result.is_err());
+    }
+}
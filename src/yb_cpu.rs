@@ -1,16 +1,14 @@
-use std::collections::BTreeMap;
 //use clap::value_parser;
 use prometheus_parse::{Value, Sample};
-use itertools::Itertools;
 use log::*;
 
-use crate::Statistic;
+use crate::{Statistic, Statistics, Row, time_header, StatisticError};
 
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     // This is the list of Yugabyte statistics that are stored for CPU:
     // "cpu_stime" |
@@ -25,25 +23,14 @@ pub fn process_statistic(
         Value::Untyped(value) => value,
         // Value::Counter is the new YugabyteDB prometheus-metrics type
         Value::Counter(value) => value,
-        _ => {
-            panic!("{} value enum type should be Untyped or Counter!", sample.metric);
-        },
+        _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped or Counter" }),
     };
     //let Value::Untyped(value) = sample.value else { panic!("{} value enum type should be Untyped!", sample.metric)};
-    let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).unwrap();
+    let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "metric_type" })?;
     statistics
-        .entry((
-            hostname.to_string(),
-            sample.metric.clone(),
-            metric_type.clone(),
-            "".to_string(),
-        ))
+        .entry(hostname, &sample.metric, metric_type, "")
         .and_modify( |row| {
-            row.delta_value = value - row.last_value;
-            row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.);
-            row.last_value = value;
-            row.last_timestamp = sample.timestamp;
-            row.first_value = false;
+            row.update_counter(value, sample.timestamp);
             debug!("{}: {} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
         } )
         .or_insert(
@@ -55,36 +42,54 @@ pub fn process_statistic(
                 ..Default::default()
             });
 
+    Ok(())
 }
 
-pub fn print_yb_cpu(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn yb_cpu_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "cpu_utime" && metric_type == "server").count() > 0
+        if statistics.first_with(hostname, "cpu_utime", "server").is_some()
         {
-            let cpu_user = statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "cpu_utime" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let cpu_system = statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "cpu_stime" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
+            let cpu_user = statistics.first_with(hostname, "cpu_utime", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let cpu_system = statistics.first_with(hostname, "cpu_stime", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
             let cpu_total = cpu_user + cpu_system;
-            let time = statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "cpu_utime" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap();
-            println!("{:50} {:8} {:10.3} {:10.3} {:10.3}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     cpu_user / 1000.,
-                     cpu_system / 1000.,
-                     cpu_total / 1000.,
-            );
+            let time = statistics.first_with(hostname, "cpu_utime", "server").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                cpu_user / 1000.,
+                cpu_system / 1000.,
+                cpu_total / 1000.,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_yb_cpu_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:50} {:8} {:10.3} {:10.3} {:10.3}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+    );
 }
 
+pub const YB_CPU_FIELDS: &[&str] = &["user_per_s", "sys_per_s", "tot_per_s"];
 pub fn print_yb_cpu_header()
 {
     println!("{:50} {:8} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "user/s",
              "sys/s",
              "tot/s",
@@ -1,15 +1,16 @@
 //use std::collections::BTreeMap;
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
 //use crate::Statistic;
-use crate::{CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, HistoricalData, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_RIGHT, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, Statistic};
+use crate::{CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, HistoricalData, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_RIGHT, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, Statistic, Statistics, Row, time_header, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct YbIoDetails {
     pub glog_info_messages: f64,
     pub glog_warning_messages: f64,
@@ -40,8 +41,8 @@ pub struct YbIoDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     match sample.metric.as_str()
     {
@@ -58,20 +59,14 @@ pub fn process_statistic(
                 Value::Untyped(value) => value,
                 // Value::Counter is the new YugabyteDB prometheus-metrics type
                 Value::Counter(value) => value,
-                _ => {
-                    panic!("{} value enum type should be Untyped or Counter!", sample.metric);
-                },
+                _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped or Counter" }),
             };
             //let Value::Untyped(value) = sample.value else { panic!("{} value enum type should be Untyped!", sample.metric)};
-            let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).unwrap();
+            let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "metric_type" })?;
             statistics
-                .entry(( hostname.to_string(), sample.metric.clone(), metric_type.to_string(), "".to_string() ))
+                .entry(hostname, &sample.metric, metric_type, "")
                 .and_modify( |row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
-                    row.first_value = false;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{}: {} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 } )
                 .or_insert(
@@ -115,20 +110,15 @@ pub fn process_statistic(
                 // See: [DocDB] All rocksdb_block_cache prometheus statistics are wrongly annotated as gauges, should be counters
                 // (https://github.com/yugabyte/yugabyte-db/issues/18040)
                 Value::Gauge(value) => value,
-                _ => {
-                    panic!("{} value enum type should be Untyped or Counter!", sample.metric);
-                },
+                _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped, Counter or Gauge" }),
             };
             //let Value::Untyped(value) = sample.value else { panic!("{} value enum type should be Untyped!", sample.metric)};
-            let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).unwrap();
-            let table_id = sample.labels.iter().find(|(label, _)| *label == "table_id").map(|(_, value)| value).unwrap();
+            let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "metric_type" })?;
+            let table_id = sample.labels.iter().find(|(label, _)| *label == "table_id").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "table_id" })?;
             statistics
-                .entry(( hostname.to_string(), sample.metric.clone(), metric_type.to_string(), table_id.to_string() ))
+                .entry(hostname, &sample.metric, metric_type, table_id)
                 .and_modify( |row| {
-                    row.delta_value = value - row.last_value;
-                    row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.0);
-                    row.last_value = value;
-                    row.last_timestamp = sample.timestamp;
+                    row.update_counter(value, sample.timestamp);
                     debug!("{} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
                 } )
                 .or_insert(
@@ -142,66 +132,102 @@ pub fn process_statistic(
         },
         &_ => {},
     }
+    Ok(())
 }
 
-pub fn print_yb_io(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn yb_io_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_info_messages" && metric_type == "server").count() > 0
+        if statistics.first_with(hostname, "glog_info_messages", "server").is_some()
         {
-            let info_messages = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_info_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap();
-            let warning_messages = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_warning_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let error_messages = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_error_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).unwrap_or_default();
-            let guaranteed_last_timestamp = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "glog_info_messages" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
+            let info_messages = statistics.first_with(hostname, "glog_info_messages", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let warning_messages = statistics.first_with(hostname, "glog_warning_messages", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let error_messages = statistics.first_with(hostname, "glog_error_messages", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let guaranteed_last_timestamp = statistics.first_with(hostname, "glog_info_messages", "server").map(|statistic| statistic.last_timestamp).unwrap_or_default();
             // I see random use of metric_type table and tablet, so all I can do is not filter a specific one and take them both.
             // I hope there is no double counting in that....
-            let log_bytes_logged: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_bytes_logged" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let log_reader_bytes_read: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_reader_bytes_read" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_flush_write_bytes: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_flush_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let intentsdb_rocksdb_flush_write_bytes: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_flush_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_compact_read_bytes: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_compact_read_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let intentsdb_rocksdb_compact_read_bytes: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_compact_read_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_compact_write_bytes: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_compact_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let intentsdb_rocksdb_compact_write_bytes: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "intentsdb_rocksdb_compact_write_bytes" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let log_cache_disk_reads: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_cache_disk_reads" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let log_sync_latency_count: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_sync_latency_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let log_sync_latency_sum: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_sync_latency_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let log_append_latency_count: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_append_latency_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let log_append_latency_sum: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "log_append_latency_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_write_raw_block_micros_count: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_write_raw_block_micros_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_write_raw_block_micros_sum: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_write_raw_block_micros_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_sst_read_micros_count: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_sst_read_micros_count" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
-            let rocksdb_sst_read_micros_sum: f64 = statistics.iter().filter(|((host, metric, _, _), statistic)| host == hostname && metric == "rocksdb_sst_read_micros_sum" && statistic.last_timestamp == guaranteed_last_timestamp).map(|((_, _, _, _), statistic)| statistic.per_second_value).sum();
+            let log_bytes_logged: f64 = statistics.rows(hostname, "log_bytes_logged").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let log_reader_bytes_read: f64 = statistics.rows(hostname, "log_reader_bytes_read").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_flush_write_bytes: f64 = statistics.rows(hostname, "rocksdb_flush_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let intentsdb_rocksdb_flush_write_bytes: f64 = statistics.rows(hostname, "intentsdb_rocksdb_flush_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_compact_read_bytes: f64 = statistics.rows(hostname, "rocksdb_compact_read_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let intentsdb_rocksdb_compact_read_bytes: f64 = statistics.rows(hostname, "intentsdb_rocksdb_compact_read_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_compact_write_bytes: f64 = statistics.rows(hostname, "rocksdb_compact_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let intentsdb_rocksdb_compact_write_bytes: f64 = statistics.rows(hostname, "intentsdb_rocksdb_compact_write_bytes").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let log_cache_disk_reads: f64 = statistics.rows(hostname, "log_cache_disk_reads").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let log_sync_latency_count: f64 = statistics.rows(hostname, "log_sync_latency_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let log_sync_latency_sum: f64 = statistics.rows(hostname, "log_sync_latency_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let log_append_latency_count: f64 = statistics.rows(hostname, "log_append_latency_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let log_append_latency_sum: f64 = statistics.rows(hostname, "log_append_latency_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_write_raw_block_micros_count: f64 = statistics.rows(hostname, "rocksdb_write_raw_block_micros_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_write_raw_block_micros_sum: f64 = statistics.rows(hostname, "rocksdb_write_raw_block_micros_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_sst_read_micros_count: f64 = statistics.rows(hostname, "rocksdb_sst_read_micros_count").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let rocksdb_sst_read_micros_sum: f64 = statistics.rows(hostname, "rocksdb_sst_read_micros_sum").filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let block_cache_hit: f64 = ["rocksdb_block_cache_hit", "intentsdb_rocksdb_block_cache_hit"].iter().flat_map(|metric| statistics.rows(hostname, metric)).filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            let block_cache_miss: f64 = ["rocksdb_block_cache_miss", "intentsdb_rocksdb_block_cache_miss"].iter().flat_map(|metric| statistics.rows(hostname, metric)).filter(|(_, statistic)| statistic.last_timestamp == guaranteed_last_timestamp).map(|(_, statistic)| statistic.per_second_value).sum();
+            // without block cache lookups in the interval, there is no hit percentage.
+            let block_cache_hit_percent = if block_cache_hit + block_cache_miss > 0. { block_cache_hit / (block_cache_hit + block_cache_miss) * 100. } else { f64::NAN };
 
-            println!("{:50} {:8} {:10.2} {:10.2}|{:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}|{:10.2}|{:10.2} {:10.2}|{:10.2} {:10.2} {:10.2} {:10.2}",
-                     hostname,
-                     guaranteed_last_timestamp.format("%H:%M:%S"),
-                     info_messages,
-                     warning_messages + error_messages,
-                     log_bytes_logged / (1024.*1024.),
-                     log_reader_bytes_read / (1024.*1024.),
-                     log_append_latency_count,
-                     log_cache_disk_reads,
-                     (log_append_latency_sum / log_append_latency_count).max(0.) / 1000.,
-                     (log_sync_latency_sum / log_sync_latency_count).max(0.) / 1000.,
-                     (rocksdb_flush_write_bytes + intentsdb_rocksdb_flush_write_bytes) / (1024.*1024.),
-                     (rocksdb_compact_read_bytes + intentsdb_rocksdb_compact_read_bytes) / (1024.*1024.),
-                     (rocksdb_compact_write_bytes + intentsdb_rocksdb_compact_write_bytes) / (1024.*1024.),
-                     rocksdb_write_raw_block_micros_count,
-                     (rocksdb_write_raw_block_micros_sum / rocksdb_write_raw_block_micros_count).max(0.) / 1000.,
-                     rocksdb_sst_read_micros_count,
-                     (rocksdb_sst_read_micros_sum / rocksdb_sst_read_micros_count).max(0.) / 1000.,
-            );
+            let values = vec![
+                info_messages,
+                warning_messages + error_messages,
+                log_bytes_logged / (1024.*1024.),
+                log_reader_bytes_read / (1024.*1024.),
+                log_append_latency_count,
+                log_cache_disk_reads,
+                (log_append_latency_sum / log_append_latency_count).max(0.) / 1000.,
+                (log_sync_latency_sum / log_sync_latency_count).max(0.) / 1000.,
+                (rocksdb_flush_write_bytes + intentsdb_rocksdb_flush_write_bytes) / (1024.*1024.),
+                (rocksdb_compact_read_bytes + intentsdb_rocksdb_compact_read_bytes) / (1024.*1024.),
+                (rocksdb_compact_write_bytes + intentsdb_rocksdb_compact_write_bytes) / (1024.*1024.),
+                rocksdb_write_raw_block_micros_count,
+                (rocksdb_write_raw_block_micros_sum / rocksdb_write_raw_block_micros_count).max(0.) / 1000.,
+                rocksdb_sst_read_micros_count,
+                (rocksdb_sst_read_micros_sum / rocksdb_sst_read_micros_count).max(0.) / 1000.,
+                block_cache_hit_percent,
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: guaranteed_last_timestamp, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_yb_io_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:50} {:8} {:10.2} {:10.2}|{:10.2} {:10.2} {:10.2} {:10.2} {:10.2} {:10.2}|{:10.2}|{:10.2} {:10.2}|{:10.2} {:10.2} {:10.2} {:10.2}|{:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+             values[8],
+             values[9],
+             values[10],
+             values[11],
+             values[12],
+             values[13],
+             values[14],
+             values[15],
+    );
 }
 
+pub const YB_IO_FIELDS: &[&str] = &["glog_info", "glog_warn_err", "log_w_mbps", "log_r_mbps", "log_w_iops", "log_r_iops", "log_w_lat_ms", "log_sync_lat_ms", "flush_w_mbps", "compaction_r_mbps", "compaction_w_mbps", "rocksdb_w_iops", "rocksdb_w_lat_ms", "rocksdb_r_iops", "rocksdb_r_lat_ms", "block_cache_hit_percent"];
 pub fn print_yb_io_header()
 {
-    println!("{:50} {:8} {:10} {:>10}|{:10} {:10} {:10} {:10} {:10} {:>10}|{:>10}|{:10} {:>10}|{:10} {:10} {:10} {:>10}",
+    println!("{:50} {:8} {:10} {:>10}|{:10} {:10} {:10} {:10} {:10} {:>10}|{:>10}|{:10} {:>10}|{:10} {:10} {:10} {:>10}|{:>10}",
              "",
              "",
              "",
@@ -219,10 +245,11 @@ pub fn print_yb_io_header()
              "",
              "",
              "rocksdb",
+             "blockcache",
     );
-    println!("{:50} {:8} {:>10} {:>10}|{:>10} {:>10} {:>10} {:>10} {:>10} {:>10}|{:>10}|{:>10} {:>10}|{:>10} {:>10} {:>10} {:>10}",
+    println!("{:50} {:8} {:>10} {:>10}|{:>10} {:>10} {:>10} {:>10} {:>10} {:>10}|{:>10}|{:>10} {:>10}|{:>10} {:>10} {:>10} {:>10}|{:>10}",
              "hostname",
-             "time",
+             time_header(),
              "info",
              "warn+err",
              "W_MBPS",
@@ -238,6 +265,7 @@ pub fn print_yb_io_header()
              "W_lat(ms)",
              "R_IOPS",
              "R_lat(ms)",
+             "hit%",
     );
 }
 
@@ -779,7 +807,7 @@ pub fn create_yb_io_plots(
         let high_value_block_cache = unlocked_historical_data.yb_io_details
             .iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| (row.intentsdb_rocksdb_block_cache_hit + row.intentsdb_rocksdb_block_cache_miss + row.rocksdb_block_cache_hit + row.rocksdb_block_cache_miss))
+            .map(|((_, _), row)| row.intentsdb_rocksdb_block_cache_hit + row.intentsdb_rocksdb_block_cache_miss + row.rocksdb_block_cache_hit + row.rocksdb_block_cache_miss)
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         multiroot[3].fill(&WHITE).unwrap();
@@ -887,3 +915,39 @@ pub fn create_yb_io_plots(
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yb_io_statistics(
+        block_cache: &[(&str, &str, f64)],
+    ) -> Statistics
+    {
+        let mut statistics = Statistics::default();
+        statistics.entry("node1:9000:prometheus-metrics", "glog_info_messages", "server", "").or_insert(Statistic::default());
+        for (metric, table_id, per_second_value) in block_cache
+        {
+            statistics.entry("node1:9000:prometheus-metrics", metric, "table", table_id).or_insert(Statistic { per_second_value: *per_second_value, ..Default::default() });
+        }
+        statistics
+    }
+
+    #[test]
+    fn block_cache_hit_percent() {
+        let statistics = yb_io_statistics(&[
+            ("rocksdb_block_cache_hit", "t1", 60.),
+            ("rocksdb_block_cache_hit", "t2", 20.),
+            ("rocksdb_block_cache_miss", "t1", 15.),
+            ("intentsdb_rocksdb_block_cache_hit", "t1", 10.),
+            ("intentsdb_rocksdb_block_cache_miss", "t1", 7.5),
+        ]);
+        assert_eq!(yb_io_rows(&statistics)[0].values[15], 80.);
+    }
+
+    #[test]
+    fn block_cache_hit_percent_without_lookups() {
+        let statistics = yb_io_statistics(&[("rocksdb_block_cache_hit", "t1", 0.), ("rocksdb_block_cache_miss", "t1", 0.)]);
+        assert!(yb_io_rows(&statistics)[0].values[15].is_nan());
+    }
+}
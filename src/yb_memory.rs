@@ -1,13 +1,14 @@
-use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 use prometheus_parse::{Value, Sample};
 use itertools::Itertools;
 use log::*;
 use plotters::prelude::*;
 use plotters::chart::SeriesLabelPosition::UpperLeft;
+use serde::{Serialize, Deserialize};
 
-use crate::{Statistic, HistoricalData, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE};
+use crate::{Statistic, Statistics, Row, time_header, HistoricalData, LABEL_AREA_SIZE_LEFT, LABEL_AREA_SIZE_BOTTOM, LABEL_AREA_SIZE_RIGHT, CAPTION_STYLE_FONT, CAPTION_STYLE_FONT_SIZE, MESH_STYLE_FONT, MESH_STYLE_FONT_SIZE, LABELS_STYLE_FONT, LABELS_STYLE_FONT_SIZE, StatisticError};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct YbMemoryDetails {
     pub generic_heap: f64,
     pub generic_allocated: f64,
@@ -28,8 +29,8 @@ pub struct YbMemoryDetails {
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     let value = match sample.value
     {
@@ -37,20 +38,13 @@ pub fn process_statistic(
         Value::Untyped(value) => value,
         // Value::Gauge is the new YugabyteDB prometheus-metrics type
         Value::Gauge(value) => value,
-        _ => {
-            panic!("{} value enum type should be Untyped or Gauge!", sample.metric);
-        },
+        _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped or Gauge" }),
     };
     //let Value::Untyped(value) = sample.value else { panic!("{} value enum type should be Untyped!", sample.metric)};
-    let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).unwrap();
+    let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "metric_type" })?;
 
     statistics
-        .entry((
-            hostname.to_string(),
-            sample.metric.clone(),
-            metric_type.to_string(),
-            "".to_string(),
-        ))
+        .entry(hostname, &sample.metric, metric_type, "")
         .and_modify( |row| {
             row.last_value = value;
             row.last_timestamp = sample.timestamp;
@@ -66,61 +60,90 @@ pub fn process_statistic(
                 ..Default::default()
             }
         );
+    Ok(())
 }
 
-pub fn print_yb_memory(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn yb_memory_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().any(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_heap_size" && metric_type == "server" )
+        if statistics.first_with(hostname, "generic_heap_size", "server").is_some()
         {
-            let generic_heap_size = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_heap_size" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let generic_allocated = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_current_allocated_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let tcmalloc_pageheap_free_bytes = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_pageheap_free_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let tcmalloc_max_total_thread_cache_bytes = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_max_total_thread_cache_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let tcmalloc_current_total_thread_cache_bytes = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_current_total_thread_cache_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let tcmalloc_pageheap_unmapped_bytes = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcmalloc_pageheap_unmapped_bytes" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_call = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Call" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_read_buffer = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_compressed_read_buffer = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Compressed_Read_Buffer" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_tablets = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Tablets" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master does not have tablets
-            let mem_tracker_log_cache = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_log_cache" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_blockbasedtable = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_BlockBasedTable" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_compressed_read_buffer_receive = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Compressed_Read_Buffer_Receive" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_read_buffer_inbound_rpc_sending = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Inbound_RPC_Sending" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
-            let mem_tracker_read_buffer_inbound_rpc_receive = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Inbound_RPC_Receive" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
-            let mem_tracker_read_buffer_inbound_rpc_reading = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Inbound_RPC_Reading" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
-            let mem_tracker_read_buffer_outbound_rpc_queueing = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Queueing" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap();
-            let mem_tracker_read_buffer_outbound_rpc_receive = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Receive" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master follower does not have this
-            let mem_tracker_read_buffer_outbound_rpc_sending = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Sending" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master follower does not have this
-            let mem_tracker_read_buffer_outbound_rpc_reading = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "mem_tracker_Read_Buffer_Outbound_RPC_Reading" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_value).unwrap_or_default(); // master follower does not have this
+            let generic_heap_size = statistics.first_with(hostname, "generic_heap_size", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let generic_allocated = statistics.first_with(hostname, "generic_current_allocated_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let tcmalloc_pageheap_free_bytes = statistics.first_with(hostname, "tcmalloc_pageheap_free_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let tcmalloc_max_total_thread_cache_bytes = statistics.first_with(hostname, "tcmalloc_max_total_thread_cache_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let tcmalloc_current_total_thread_cache_bytes = statistics.first_with(hostname, "tcmalloc_current_total_thread_cache_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let tcmalloc_pageheap_unmapped_bytes = statistics.first_with(hostname, "tcmalloc_pageheap_unmapped_bytes", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker = statistics.first_with(hostname, "mem_tracker", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_call = statistics.first_with(hostname, "mem_tracker_Call", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_read_buffer = statistics.first_with(hostname, "mem_tracker_Read_Buffer", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_compressed_read_buffer = statistics.first_with(hostname, "mem_tracker_Compressed_Read_Buffer", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_tablets = statistics.first_with(hostname, "mem_tracker_Tablets", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master does not have tablets
+            let mem_tracker_log_cache = statistics.first_with(hostname, "mem_tracker_log_cache", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_blockbasedtable = statistics.first_with(hostname, "mem_tracker_BlockBasedTable", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_compressed_read_buffer_receive = statistics.first_with(hostname, "mem_tracker_Compressed_Read_Buffer_Receive", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_read_buffer_inbound_rpc_sending = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Inbound_RPC_Sending", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
+            let mem_tracker_read_buffer_inbound_rpc_receive = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Inbound_RPC_Receive", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
+            let mem_tracker_read_buffer_inbound_rpc_reading = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Inbound_RPC_Reading", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // doesn't exist when no RPC calls have been made
+            let mem_tracker_read_buffer_outbound_rpc_queueing = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Queueing", "server").map(|statistic| statistic.last_value).unwrap_or_default();
+            let mem_tracker_read_buffer_outbound_rpc_receive = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Receive", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master follower does not have this
+            let mem_tracker_read_buffer_outbound_rpc_sending = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Sending", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master follower does not have this
+            let mem_tracker_read_buffer_outbound_rpc_reading = statistics.first_with(hostname, "mem_tracker_Read_Buffer_Outbound_RPC_Reading", "server").map(|statistic| statistic.last_value).unwrap_or_default(); // master follower does not have this
             let mem_tracker_independent_allocations = mem_tracker_compressed_read_buffer_receive + mem_tracker_read_buffer_inbound_rpc_sending + mem_tracker_read_buffer_inbound_rpc_receive + mem_tracker_read_buffer_inbound_rpc_reading + mem_tracker_read_buffer_outbound_rpc_queueing + mem_tracker_read_buffer_outbound_rpc_receive + mem_tracker_read_buffer_outbound_rpc_sending + mem_tracker_read_buffer_outbound_rpc_reading;
-            let time = statistics.iter().find(|((host, metric, metric_type, _), _)| host == hostname && metric == "generic_heap_size" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_timestamp).unwrap();
-            println!("{:50} {:8} {:10.0} {:10.0}|{:10.0} {:10.0} {:10.0} {:10.0}|{:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     generic_heap_size / (1024.*1024.),
-                     generic_allocated / (1024.*1024.),
-                     tcmalloc_pageheap_free_bytes / (1024.*1024.),
-                     tcmalloc_max_total_thread_cache_bytes / (1024.*1024.),
-                     tcmalloc_current_total_thread_cache_bytes / (1024.*1024.),
-                     tcmalloc_pageheap_unmapped_bytes / (1024.*1024.),
-                     mem_tracker / (1024.*1024.),
-                     mem_tracker_call / (1024.*1024.),
-                     mem_tracker_read_buffer / (1024.*1024.),
-                     mem_tracker_compressed_read_buffer / (1024.*1024.),
-                     mem_tracker_tablets / (1024.*1024.),
-                     mem_tracker_log_cache / (1024.*1024.),
-                     mem_tracker_blockbasedtable / (1024.*1024.),
-                     mem_tracker_independent_allocations / (1024.*1024.),
-            );
+            let time = statistics.first_with(hostname, "generic_heap_size", "server").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                generic_heap_size / (1024.*1024.),
+                generic_allocated / (1024.*1024.),
+                tcmalloc_pageheap_free_bytes / (1024.*1024.),
+                tcmalloc_max_total_thread_cache_bytes / (1024.*1024.),
+                tcmalloc_current_total_thread_cache_bytes / (1024.*1024.),
+                tcmalloc_pageheap_unmapped_bytes / (1024.*1024.),
+                mem_tracker / (1024.*1024.),
+                mem_tracker_call / (1024.*1024.),
+                mem_tracker_read_buffer / (1024.*1024.),
+                mem_tracker_compressed_read_buffer / (1024.*1024.),
+                mem_tracker_tablets / (1024.*1024.),
+                mem_tracker_log_cache / (1024.*1024.),
+                mem_tracker_blockbasedtable / (1024.*1024.),
+                mem_tracker_independent_allocations / (1024.*1024.),
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_yb_memory_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:50} {:8} {:10.0} {:10.0}|{:10.0} {:10.0} {:10.0} {:10.0}|{:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0} {:10.0}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+             values[3],
+             values[4],
+             values[5],
+             values[6],
+             values[7],
+             values[8],
+             values[9],
+             values[10],
+             values[11],
+             values[12],
+             values[13],
+    );
 }
 
+pub const YB_MEMORY_FIELDS: &[&str] = &["generic_total", "generic_allocated", "tcmalloc_ph_free", "tcmalloc_tc_total", "tcmalloc_tc_current", "tcmalloc_ph_unmapd", "memtrackers_root", "memtrackers_call", "memtrackers_rd_buf", "memtrackers_c_rd_buf", "memtrackers_tablets", "memtrackers_log_cache", "memtrackers_bbt", "memtrackers_indep"];
 pub fn print_yb_memory_header()
 {
     println!("{:50} {:8} {:>10} {:>10}|{:>10} {:>10} {:>10} {:>10}|{:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
@@ -143,7 +166,7 @@ pub fn print_yb_memory_header()
     );
     println!("{:50} {:8} {:>10} {:>10}|{:>10} {:>10} {:>10} {:>10}|{:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "total",
              "allocated",
              "ph_free",
@@ -183,7 +206,7 @@ pub fn create_yb_memory_plots(
         let low_value: f64 = 0.0;
         let high_value = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _),_)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.generic_heap / (1024.*1024.))
+            .map(|(_, row)| row.generic_heap / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let ((_, _), latest) = unlocked_historical_data.yb_memory_details.iter()
@@ -211,12 +234,12 @@ pub fn create_yb_memory_plots(
             .unwrap();
         let min_generic_heap = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.generic_heap / (1024.*1024.))
+            .map(|(_, row)| row.generic_heap / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_generic_heap = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.generic_heap / (1024.*1024.))
+            .map(|(_, row)| row.generic_heap / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -229,12 +252,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(1).filled()));
         let min_generic_allocated = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.generic_allocated / (1024.*1024.))
+            .map(|(_, row)| row.generic_allocated / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_generic_allocated = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.generic_allocated / (1024.*1024.))
+            .map(|(_, row)| row.generic_allocated / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -249,12 +272,12 @@ pub fn create_yb_memory_plots(
 
         let min_pageheap_free = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.tcmalloc_pageheap_free / (1024.*1024.))
+            .map(|(_, row)| row.tcmalloc_pageheap_free / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_pageheap_free = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.tcmalloc_pageheap_free / (1024.*1024.))
+            .map(|(_, row)| row.tcmalloc_pageheap_free / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -268,12 +291,12 @@ pub fn create_yb_memory_plots(
 
         let min_tcmalloc_current_thread_cache = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.tcmalloc_current_total_thread_cache / (1024.*1024.))
+            .map(|(_, row)| row.tcmalloc_current_total_thread_cache / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_tcmalloc_current_thread_cache = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.tcmalloc_current_total_thread_cache / (1024.*1024.))
+            .map(|(_, row)| row.tcmalloc_current_total_thread_cache / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -286,12 +309,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(12).filled()));
         let min_mem_tracker_independent = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_independent_allocs / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_independent_allocs / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_independent = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_independent_allocs / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_independent_allocs / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -308,12 +331,12 @@ pub fn create_yb_memory_plots(
         //
         let min_mem_tracker = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -327,12 +350,12 @@ pub fn create_yb_memory_plots(
 
         let min_mem_tracker_call = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_call / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_call / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_call = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_call / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_call / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -345,12 +368,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(9).filled()));
         let min_mem_tracker_read_buffer = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_read_buffer / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_read_buffer / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_read_buffer = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_read_buffer / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_read_buffer / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -363,12 +386,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(8).filled()));
         let min_mem_tracker_compressed_read_buffer = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_compressed_read_buffer / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_compressed_read_buffer / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_compressed_read_buffer = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_compressed_read_buffer / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_compressed_read_buffer / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -381,12 +404,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(7).filled()));
         let min_mem_tracker_tablets = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_tablets / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_tablets / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_tablets = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_tablets / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_tablets / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -399,12 +422,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(6).filled()));
         let min_mem_tracker_log_cache = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_log_cache / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_log_cache / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_log_cache = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_log_cache / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_log_cache / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -417,12 +440,12 @@ pub fn create_yb_memory_plots(
             .legend(move |(x, y)| Rectangle::new([(x - 3, y - 3), (x + 3, y + 3)], Palette99::pick(5).filled()));
         let min_mem_tracker_blockbasedtable = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_blockbasedtable / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_blockbasedtable / (1024.*1024.))
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         let max_mem_tracker_blockbasedtable = unlocked_historical_data.yb_memory_details.iter()
             .filter(|((hostname, _), _)| hostname == filter_hostname)
-            .map(|((_, _), row)| row.mem_tracker_blockbasedtable / (1024.*1024.))
+            .map(|(_, row)| row.mem_tracker_blockbasedtable / (1024.*1024.))
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         contextarea.draw_series(AreaSeries::new(unlocked_historical_data.yb_memory_details.iter()
@@ -1,15 +1,13 @@
-use std::collections::BTreeMap;
 use prometheus_parse::{Value, Sample};
-use itertools::Itertools;
 use log::*;
 
-use crate::Statistic;
+use crate::{Statistic, Statistics, Row, time_header, StatisticError};
 
 pub fn process_statistic(
     sample: &Sample,
     hostname: &str,
-    statistics: &mut BTreeMap<(String, String, String, String), Statistic>,
-)
+    statistics: &mut Statistics,
+) -> Result<(), StatisticError>
 {
     let value = match sample.value
     {
@@ -17,25 +15,14 @@ pub fn process_statistic(
         Value::Untyped(value) => value,
         // Value::Counter is the new YugabyteDB prometheus-metrics type
         Value::Counter(value) => value,
-        _ => {
-            panic!("{} value enum type should be Untyped or Counter!", sample.metric);
-        },
+        _ => return Err(StatisticError::WrongValueType { metric: sample.metric.clone(), expected: "Untyped or Counter" }),
     };
     //let Value::Untyped(value) = sample.value else { panic!("{} value enum type should be Untyped!", sample.metric)};
-    let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).unwrap();
+    let metric_type = sample.labels.iter().find(|(label, _)| *label == "metric_type").map(|(_, value)| value).ok_or_else(|| StatisticError::MissingLabel { metric: sample.metric.clone(), label: "metric_type" })?;
     statistics
-        .entry((
-            hostname.to_string(),
-            sample.metric.clone(),
-            metric_type.clone(),
-            "".to_string(),
-        ))
+        .entry(hostname, &sample.metric, metric_type, "")
         .and_modify( |row| {
-            row.delta_value = value - row.last_value;
-            row.per_second_value = row.delta_value / (sample.timestamp.signed_duration_since(row.last_timestamp).num_milliseconds() as f64 / 1000.);
-            row.last_value = value;
-            row.last_timestamp = sample.timestamp;
-            row.first_value = false;
+            row.update_counter(value, sample.timestamp);
             debug!("{}: {} last_value: {}, last_timestamp: {}, delta_value: {}, per_second_value: {}", hostname, sample.metric, row.last_value, row.last_timestamp, row.delta_value, row.per_second_value);
         } )
         .or_insert(
@@ -46,36 +33,54 @@ pub fn process_statistic(
                 first_value: true,
                 ..Default::default()
             });
+    Ok(())
 }
 
-pub fn print_yb_network(
-    statistics: &BTreeMap<(String, String, String, String), Statistic>,
-)
+pub fn yb_network_rows(
+    statistics: &Statistics,
+) -> Vec<Row>
 {
-    for hostname in statistics.iter().map(|((hostname, _, _, _), _)| hostname).unique()
+    let mut rows = Vec::new();
+    for hostname in statistics.hosts()
     {
-        if statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcp_bytes_received" && metric_type == "server").count() > 0
+        if statistics.first_with(hostname, "tcp_bytes_received", "server").is_some()
         {
-            let network_bytes_received = statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcp_bytes_received" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
-            let network_bytes_sent = statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcp_bytes_sent" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.per_second_value).next().unwrap();
+            let network_bytes_received = statistics.first_with(hostname, "tcp_bytes_received", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
+            let network_bytes_sent = statistics.first_with(hostname, "tcp_bytes_sent", "server").map(|statistic| statistic.per_second_value).unwrap_or_default();
             let network_bytes_total = network_bytes_received + network_bytes_sent;
-            let time = statistics.iter().filter(|((host, metric, metric_type, _), _)| host == hostname && metric == "tcp_bytes_received" && metric_type == "server").map(|((_, _, _, _), statistic)| statistic.last_timestamp).next().unwrap();
-            println!("{:50} {:8} {:10.2} {:10.2} {:10.2}",
-                     hostname,
-                     time.format("%H:%M:%S"),
-                     network_bytes_received / (1024.*1024.),
-                     network_bytes_sent / (1024.*1024.),
-                     network_bytes_total / (1024.*1024.),
-            );
+            let time = statistics.first_with(hostname, "tcp_bytes_received", "server").map(|statistic| statistic.last_timestamp).unwrap_or_default();
+            let values = vec![
+                network_bytes_received / (1024.*1024.),
+                network_bytes_sent / (1024.*1024.),
+                network_bytes_total / (1024.*1024.),
+            ];
+            rows.push(Row { hostname: hostname.to_string(), timestamp: time, label: String::new(), labels: Vec::new(), values });
         }
     }
+    rows
+}
+
+pub fn print_yb_network_row(
+    hostname: &str,
+    time: &str,
+    values: &[f64],
+)
+{
+    println!("{:50} {:8} {:10.2} {:10.2} {:10.2}",
+             hostname,
+             time,
+             values[0],
+             values[1],
+             values[2],
+    );
 }
 
+pub const YB_NETWORK_FIELDS: &[&str] = &["rxmb_per_s", "txmb_per_s", "totmb_per_s"];
 pub fn print_yb_network_header()
 {
     println!("{:50} {:8} {:>10} {:>10} {:>10}",
              "hostname",
-             "time",
+             time_header(),
              "rxMB/s",
              "txMB/s",
              "totMB/s",